@@ -0,0 +1,202 @@
+//! Wire conventions shared by `ser::Serializer` and `de::Deserializer`.
+//!
+//! `Config::json4web()` is what `ser::to_string` and `de::from_str` use.
+//! `Config::standard()` talks plain JSON to clients that don't know json4web.
+
+use alloc::string::String;
+use alloc::vec::Vec;
+
+use crate::{Error, Result};
+
+/// How many arrays and objects `ser::Serializer` and `de::Deserializer`
+/// allow to be open at once by default.
+pub(crate) const MAX_DEPTH: usize = 128;
+
+/// How `bool` is written and which spellings are read back.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Bools {
+    /// Write `1`/`0`; read `1`, `0`, `true` and `false`.
+    Numeric,
+    /// Write and read only `true`/`false`.
+    Literal,
+}
+
+/// How `i64`, `u64`, `i128` and `u128` are written and read back.
+/// Narrower integers are always bare numbers.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Integers {
+    /// As quoted strings, so JavaScript numbers don't lose precision.
+    Quoted,
+    /// As bare numbers.
+    Bare,
+    /// As bare numbers within +-(2^53 - 1), JavaScript's safe integer range,
+    /// and as quoted strings beyond it. Either form is read back.
+    QuoteUnsafe,
+}
+
+/// How NaN and infinite floats are written and read back.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum NonFinite {
+    /// Fail with `Error::NonFiniteFloat`; `null` is not read as a float either.
+    Error,
+    /// Write `null`, which reads back as NaN.
+    Null,
+    /// Write the strings `"NaN"`, `"Infinity"` and `"-Infinity"`, like JavaScript's `String(x)`.
+    Strings,
+}
+
+/// How byte arrays, e.g. `serde_bytes::ByteBuf`, are written into strings.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ByteEncoding {
+    /// URL-safe base64 with `=` padding.
+    Base64UrlSafe,
+    /// URL-safe base64 without padding, as used in JWTs.
+    Base64UrlSafeNoPad,
+    /// Standard base64 with `+`, `/` and `=` padding.
+    Base64Standard,
+    /// Lowercase hex. Either case is read back.
+    Hex,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Config {
+    pub(crate) bools: Bools,
+    pub(crate) integers: Integers,
+    pub(crate) non_finite: NonFinite,
+    pub(crate) bytes: ByteEncoding,
+    pub(crate) lenient_base64: bool,
+    pub(crate) any_quoted_integers: bool,
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Config::json4web()
+    }
+}
+
+impl Config {
+    /// `1`/`0` booleans, quoted 64-bit integers and `null` for non-finite floats.
+    pub fn json4web() -> Self {
+        Config {
+            bools: Bools::Numeric,
+            integers: Integers::Quoted,
+            non_finite: NonFinite::Null,
+            bytes: ByteEncoding::Base64UrlSafe,
+            lenient_base64: false,
+            any_quoted_integers: false,
+        }
+    }
+
+    /// Standard JSON: `true`/`false`, bare integers and `null` for non-finite floats.
+    pub fn standard() -> Self {
+        Config {
+            bools: Bools::Literal,
+            integers: Integers::Bare,
+            non_finite: NonFinite::Null,
+            bytes: ByteEncoding::Base64UrlSafe,
+            lenient_base64: false,
+            any_quoted_integers: false,
+        }
+    }
+
+    pub fn bools(mut self, bools: Bools) -> Self {
+        self.bools = bools;
+        self
+    }
+
+    pub fn integers(mut self, integers: Integers) -> Self {
+        self.integers = integers;
+        self
+    }
+
+    pub fn non_finite(mut self, non_finite: NonFinite) -> Self {
+        self.non_finite = non_finite;
+        self
+    }
+
+    pub fn bytes(mut self, bytes: ByteEncoding) -> Self {
+        self.bytes = bytes;
+        self
+    }
+
+    /// Makes base64 byte encodings read back both the standard and the
+    /// URL-safe alphabet. Padding is always optional when reading.
+    pub fn lenient_base64(mut self, lenient: bool) -> Self {
+        self.lenient_base64 = lenient;
+        self
+    }
+
+    /// Makes `deserialize_any` visit quoted integers that fit in 64 bits as
+    /// `u64`/`i64` instead of as strings.
+    ///
+    /// Serde buffers the input of `#[serde(untagged)]`, `#[serde(tag = "...")]`
+    /// and `#[serde(flatten)]` types through `deserialize_any` before it knows
+    /// the field types. With this on, such buffered input reads quoted 64-bit
+    /// integers back, but no longer reads a digit-only string into a `String`.
+    /// Object keys are always read as strings.
+    ///
+    /// Serde can't buffer `i128`/`u128` fields at all, nor `bool` fields under
+    /// `Bools::Numeric`: `1`/`0` is buffered as an integer, which only an integer
+    /// field takes. Use `Bools::Literal` for such types.
+    pub fn any_quoted_integers(mut self, infer: bool) -> Self {
+        self.any_quoted_integers = infer;
+        self
+    }
+
+    pub(crate) fn encode_bytes(&self, v: &[u8]) -> String {
+        match self.bytes {
+            ByteEncoding::Base64UrlSafe => base64::encode_config(v, base64::URL_SAFE),
+            ByteEncoding::Base64UrlSafeNoPad => base64::encode_config(v, base64::URL_SAFE_NO_PAD),
+            ByteEncoding::Base64Standard => base64::encode_config(v, base64::STANDARD),
+            ByteEncoding::Hex => {
+                let mut out = String::with_capacity(v.len() * 2);
+                for b in v {
+                    out.push(HEX[usize::from(b >> 4)] as char);
+                    out.push(HEX[usize::from(b & 0xf)] as char);
+                }
+                out
+            }
+        }
+    }
+
+    /// Decodes `s`, failing before it allocates if that yields more than `max_len` bytes.
+    pub(crate) fn decode_bytes(&self, s: &str, max_len: usize) -> Result<Vec<u8>> {
+        let len = match self.bytes {
+            ByteEncoding::Hex => s.len() / 2,
+            _ => s.trim_end_matches('=').len() * 3 / 4,
+        };
+        if len > max_len {
+            return Err(Error::BytesTooLong);
+        }
+        let config = match self.bytes {
+            ByteEncoding::Hex => return decode_hex(s),
+            _ if self.lenient_base64 => {
+                let url_safe: Vec<u8> = s
+                    .bytes()
+                    .map(|b| match b {
+                        b'+' => b'-',
+                        b'/' => b'_',
+                        b => b,
+                    })
+                    .collect();
+                return Ok(base64::decode_config(&url_safe, base64::URL_SAFE)?);
+            }
+            ByteEncoding::Base64UrlSafe | ByteEncoding::Base64UrlSafeNoPad => base64::URL_SAFE,
+            ByteEncoding::Base64Standard => base64::STANDARD,
+        };
+        Ok(base64::decode_config(s, config)?)
+    }
+}
+
+const HEX: &[u8; 16] = b"0123456789abcdef";
+
+fn decode_hex(s: &str) -> Result<Vec<u8>> {
+    let digit = |b: u8| (b as char).to_digit(16).ok_or(Error::InvalidHex);
+    s.as_bytes()
+        .chunks(2)
+        .map(|pair| match *pair {
+            [hi, lo] => Ok((digit(hi)? << 4 | digit(lo)?) as u8),
+            _ => Err(Error::InvalidHex),
+        })
+        .collect()
+}
@@ -1,338 +1,1039 @@
-use crate::{Error, Result};
-use alloc::borrow::Cow;
-use alloc::str::Chars;
+use crate::config::{Bools, Integers, NonFinite, MAX_DEPTH};
+use crate::read::{Read, Reference, StrRead};
+use crate::{Config, Error, Path, Position, Result, Segment};
+use alloc::boxed::Box;
 use alloc::string::String;
-use core::convert::TryFrom;
+use alloc::vec::Vec;
+use core::marker::PhantomData;
 use core::num::ParseFloatError;
 use core::num::ParseIntError;
-use core::str::FromStr;
+use core::str::{self, FromStr};
 use serde::de::{
     self, DeserializeSeed, EnumAccess, IntoDeserializer, MapAccess, SeqAccess, VariantAccess,
     Visitor,
 };
-use serde::serde_if_integer128;
 
 pub fn from_slice<'a, T>(input: &'a [u8]) -> Result<T>
 where
     T: serde::Deserialize<'a>,
 {
-    use core::str;
-    from_str(str::from_utf8(input)?)
+    from_slice_with_config(input, Config::json4web())
+}
+
+/// Like `from_slice`, following the wire conventions of `config`.
+pub fn from_slice_with_config<'a, T>(input: &'a [u8], config: Config) -> Result<T>
+where
+    T: serde::Deserialize<'a>,
+{
+    let input = str::from_utf8(input).map_err(|err| {
+        let valid = str::from_utf8(&input[..err.valid_up_to()]).unwrap_or_default();
+        Deserializer::with_read(StrRead::at_end(valid)).fix_position(err.into())
+    })?;
+    from_str_with_config(input, config)
 }
 
 pub fn from_str<'a, T>(input: &'a str) -> Result<T>
 where
     T: serde::Deserialize<'a>,
 {
-    let mut des = Deserializer(input);
-    T::deserialize(&mut des)
+    from_str_with_config(input, Config::json4web())
 }
 
-pub struct Deserializer<'de>(&'de str);
-
-fn parse_escape(chs: &mut Chars, buf: &mut String, at: &mut usize) -> Result<()> {
-    let ch = chs.next().ok_or(Error::UnexpectedEnd)?;
-    let ch = match ch {
-        '"' | '\\' | '/' => ch,
-        'b' => '\x08',
-        'f' => '\x0c',
-        'n' => '\n',
-        'r' => '\r',
-        't' => '\t',
-        'u' => {
-            let hchs = chs.by_ref().take(4);
-            let mut ch = 0u32;
-            for c in hchs {
-                ch = (ch << 4) + c.to_digit(16).ok_or(Error::InvalidUnicodeEscapeSequence)?;
-            }
-            *at += 4;
-            char::try_from(ch).map_err(|_| Error::UnexpectedUnicodeEscapeSequence(ch))?
-        }
-        token => return Err(Error::UnexpectedToken(token)),
-    };
-    *at += 1;
-    buf.push(ch);
-    Ok(())
+/// Like `from_str`, following the wire conventions of `config`.
+pub fn from_str_with_config<'a, T>(input: &'a str, config: Config) -> Result<T>
+where
+    T: serde::Deserialize<'a>,
+{
+    let mut des = Deserializer::new(input).with_config(config);
+    let value = T::deserialize(&mut des)?;
+    des.end()?;
+    Ok(value)
+}
+
+/// Deserializes a `T` from `reader`, reading no further than the end of the input.
+///
+/// The reader is consumed in chunks, so it does not need to be buffered.
+#[cfg(feature = "std")]
+pub fn from_reader<R, T>(reader: R) -> Result<T>
+where
+    R: std::io::Read,
+    T: de::DeserializeOwned,
+{
+    let mut des = Deserializer::from_reader(reader);
+    let value = T::deserialize(&mut des)?;
+    des.end()?;
+    Ok(value)
+}
+
+pub struct Deserializer<R> {
+    read: R,
+    scratch: Vec<u8>,
+    lone_surrogates: LoneSurrogates,
+    syntax: Syntax,
+    depth: usize,
+    max_depth: usize,
+    limits: Limits,
+    config: Config,
+}
+
+/// Size limits on untrusted input, see `Deserializer::max_string_len` and the like.
+#[derive(Debug, Clone, Copy)]
+struct Limits {
+    string: usize,
+    array: usize,
+    object: usize,
+    number: usize,
+    bytes: usize,
+}
+
+/// What to do with a `\\uXXXX` escape naming a UTF-16 surrogate that is not part of a pair.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LoneSurrogates {
+    /// Fail with `Error::LoneSurrogate`.
+    Reject,
+    /// Decode it as U+FFFD REPLACEMENT CHARACTER.
+    Replace,
+}
+
+/// Which grammar the input has to follow, on top of the json4web conventions.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Syntax {
+    /// RFC 8259, token by token.
+    Strict,
+    /// Also accepts leading zeros and a `-` anywhere in integers, raw control
+    /// characters in strings and unknown escapes in skipped strings.
+    Permissive,
+    /// Also accepts the JSON5 extensions most common in hand-written input:
+    /// `//` and `/* */` comments, trailing commas, single-quoted strings,
+    /// `\'` escapes, ASCII identifiers as object keys, hex integers, a leading
+    /// `+` or `.`, a trailing `.`, `Infinity` and `NaN`.
+    Json5,
 }
 
-impl<'de> Deserializer<'de> {
-    fn trim_start(&mut self) {
-        self.0 = self.0.trim_start();
+impl<'de> Deserializer<StrRead<'de>> {
+    pub fn new(input: &'de str) -> Self {
+        Deserializer::with_read(StrRead::new(input))
     }
-    fn peek_char(&self) -> Result<char> {
-        self.0.chars().next().ok_or(Error::UnexpectedEnd)
+}
+
+#[cfg(feature = "std")]
+impl<R: std::io::Read> Deserializer<crate::read::IoRead<R>> {
+    /// Strings read this way are never borrowed from the input.
+    pub fn from_reader(reader: R) -> Self {
+        Deserializer::with_read(crate::read::IoRead::new(reader))
     }
-    fn peek_u8(&self) -> Result<u8> {
-        let bytes = self.0.as_bytes();
-        if bytes.is_empty() {
-            return Err(Error::UnexpectedEnd);
+}
+
+impl<'de, R: Read<'de>> Deserializer<R> {
+    pub fn with_read(read: R) -> Self {
+        Deserializer {
+            read,
+            scratch: Vec::new(),
+            lone_surrogates: LoneSurrogates::Reject,
+            syntax: Syntax::Strict,
+            depth: 0,
+            max_depth: MAX_DEPTH,
+            limits: Limits {
+                string: usize::MAX,
+                array: usize::MAX,
+                object: usize::MAX,
+                number: usize::MAX,
+                bytes: usize::MAX,
+            },
+            config: Config::json4web(),
         }
-        Ok(bytes[0])
     }
-    fn next_char(&mut self) -> Result<char> {
-        let ch = self.peek_char()?;
-        self.0 = &self.0[ch.len_utf8()..];
-        Ok(ch)
+
+    /// Switches to the wire conventions of `config`, e.g. `Config::standard()`.
+    pub fn with_config(mut self, config: Config) -> Self {
+        self.config = config;
+        self
+    }
+
+    /// Sets how `\\u` escapes of unpaired UTF-16 surrogates are decoded.
+    /// They are rejected by default.
+    pub fn lone_surrogates(mut self, policy: LoneSurrogates) -> Self {
+        self.lone_surrogates = policy;
+        self
+    }
+
+    /// Sets which grammar the input has to follow. It is `Syntax::Strict` by default.
+    pub fn syntax(mut self, syntax: Syntax) -> Self {
+        self.syntax = syntax;
+        self
+    }
+
+    /// Sets how many arrays and objects may be open at once before
+    /// `Error::RecursionLimitExceeded`. It is 128 by default.
+    pub fn max_depth(mut self, depth: usize) -> Self {
+        self.max_depth = depth;
+        self
+    }
+
+    /// Fails with `Error::StringTooLong` on strings and keys of more than `len`
+    /// bytes, counted in the input between the quotes. There is no limit by default.
+    pub fn max_string_len(mut self, len: usize) -> Self {
+        self.limits.string = len;
+        self
+    }
+
+    /// Fails with `Error::TooManyElements` on arrays of more than `len` elements.
+    /// There is no limit by default.
+    pub fn max_array_len(mut self, len: usize) -> Self {
+        self.limits.array = len;
+        self
     }
-    fn assert_next_char(&mut self, rhs: char) -> Result<()> {
-        let ch = self.peek_char()?;
-        if ch != rhs {
-            return Err(Error::UnexpectedToken(ch));
+
+    /// Fails with `Error::TooManyKeys` on objects of more than `len` keys.
+    /// There is no limit by default.
+    pub fn max_object_len(mut self, len: usize) -> Self {
+        self.limits.object = len;
+        self
+    }
+
+    /// Fails with `Error::NumberTooLong` on numbers of more than `len` bytes,
+    /// counting the sign, point and exponent. Quoted 64 and 128-bit integers
+    /// count too. There is no limit by default.
+    pub fn max_number_len(mut self, len: usize) -> Self {
+        self.limits.number = len;
+        self
+    }
+
+    /// Fails with `Error::BytesTooLong` on byte arrays that decode to more than
+    /// `len` bytes, before decoding them. There is no limit by default.
+    pub fn max_bytes_len(mut self, len: usize) -> Self {
+        self.limits.bytes = len;
+        self
+    }
+
+    /// Checks that only whitespace remains after the last deserialized value.
+    pub fn end(&mut self) -> Result<()> {
+        self.located(|des| {
+            des.trim_start()?;
+            match des.read.peek()? {
+                None => Ok(()),
+                Some(_) => Err(Error::TrailingCharacters),
+            }
+        })
+    }
+
+    /// Turns this deserializer into an iterator over whitespace-separated
+    /// values, such as newline-delimited JSON.
+    pub fn into_stream<T>(self) -> StreamDeserializer<'de, R, T>
+    where
+        T: de::Deserialize<'de>,
+    {
+        StreamDeserializer {
+            de: self,
+            failed: false,
+            lifetime: PhantomData,
+            output: PhantomData,
         }
-        self.0 = &self.0[ch.len_utf8()..];
-        Ok(())
     }
-    fn parse_string(&mut self) -> Result<Cow<'de, str>> {
-        let mut chs = self.0.chars();
-        let first_char = chs.next().ok_or(Error::UnexpectedEnd)?;
-        if first_char != '"' {
-            return Err(Error::UnexpectedToken(first_char));
+
+    /// Consumes the `[` of an array and returns an iterator that deserializes
+    /// its elements one at a time. The closing `]` is checked once the
+    /// elements run out.
+    pub fn array_iter<T>(&mut self) -> Result<ArrayIter<'_, 'de, R, T>>
+    where
+        T: de::Deserialize<'de>,
+    {
+        self.located(|des| {
+            des.trim_start()?;
+            des.assert_next(b'[')
+        })?;
+        Ok(ArrayIter {
+            seq: CommaSeparated::new(self),
+            done: false,
+            lifetime: PhantomData,
+            output: PhantomData,
+        })
+    }
+
+    /// Returns the position of the next unread character.
+    pub fn position(&self) -> Position {
+        self.read.position()
+    }
+
+    fn fix_position(&self, err: Error) -> Error {
+        match err {
+            Error::At { .. } => err,
+            err => Error::At {
+                error: Box::new(err),
+                position: self.position(),
+                path: Path::default(),
+            },
+        }
+    }
+
+    /// Prepends `segment` to the path of an error raised inside a nested value.
+    fn fix_path(&self, err: Error, segment: Segment) -> Error {
+        match self.fix_position(err) {
+            Error::At {
+                error,
+                position,
+                mut path,
+            } => {
+                path.0.insert(0, segment);
+                Error::At {
+                    error,
+                    position,
+                    path,
+                }
+            }
+            err => err,
+        }
+    }
+
+    /// Decodes the key string that started at byte `offset`, for error paths only.
+    /// Readers that cannot go back fall back to the `copy` taken when the key was read.
+    fn key_at(&self, offset: usize, copy: &str) -> String {
+        let rest = match self.read.borrow_from(offset) {
+            Some(rest) => rest,
+            None => return String::from(copy),
+        };
+        let mut des = Deserializer::new(rest)
+            .lone_surrogates(self.lone_surrogates)
+            .syntax(self.syntax);
+        match des.parse_key() {
+            Ok(key) => String::from(&*key),
+            Err(_) => String::from("?"),
+        }
+    }
+
+    /// Remembers the string just read into scratch space when `key_at` won't be able to.
+    fn copy_key(&self, offset: usize, copy: &mut String) {
+        if self.read.borrow_from(offset).is_none() {
+            copy.clear();
+            copy.push_str(str::from_utf8(&self.scratch).unwrap_or("?"));
+        }
+    }
+
+    /// Runs `f` and attaches the current position to any error it returns.
+    fn located<T>(&mut self, f: impl FnOnce(&mut Self) -> Result<T>) -> Result<T> {
+        let result = f(self);
+        result.map_err(|err| self.fix_position(err))
+    }
+
+    /// Runs `f` one array or object deeper.
+    fn nested<T>(&mut self, f: impl FnOnce(&mut Self) -> Result<T>) -> Result<T> {
+        if self.depth >= self.max_depth {
+            return Err(Error::RecursionLimitExceeded);
         }
-        let mut at = 1;
-        let mut buf = None;
+        self.depth += 1;
+        let result = f(self);
+        self.depth -= 1;
+        result
+    }
+
+    fn trim_start(&mut self) -> Result<()> {
         loop {
-            let ch = chs.next().ok_or(Error::UnexpectedEnd)?;
-            let ch_len = ch.len_utf8();
-            if ch == '\\' {
-                if buf.is_none() {
-                    buf = Some(String::from(&self.0[1..at]));
+            match self.read.peek()? {
+                Some(b' ' | b'\t' | b'\n' | b'\r') => self.read.discard(),
+                Some(b'/') if self.syntax == Syntax::Json5 => self.skip_comment()?,
+                _ => return Ok(()),
+            }
+        }
+    }
+
+    /// Skips a `//` or `/* */` comment.
+    fn skip_comment(&mut self) -> Result<()> {
+        self.read.discard();
+        match self.peek_u8()? {
+            b'/' => {
+                while let Some(b) = self.read.next()? {
+                    if b == b'\n' {
+                        break;
+                    }
                 }
-                at += ch_len;
-                parse_escape(&mut chs, buf.as_mut().unwrap(), &mut at)?;
-                continue;
             }
-            at += ch_len;
-            if ch == '"' {
+            b'*' => {
+                self.read.discard();
+                let mut star = false;
+                loop {
+                    match self.read.next()?.ok_or(Error::UnexpectedEnd)? {
+                        b'/' if star => break,
+                        b => star = b == b'*',
+                    }
+                }
+            }
+            _ => return Err(self.peek_error()),
+        }
+        Ok(())
+    }
+    fn peek_u8(&mut self) -> Result<u8> {
+        self.read.peek()?.ok_or(Error::UnexpectedEnd)
+    }
+    /// The error for an unexpected next character.
+    /// Fails unless a number or literal is followed by whitespace, a structural
+    /// character or the end of input, so `truefalse` isn't read as two values.
+    fn peek_end_of_value(&mut self) -> Result<()> {
+        match self.read.peek()? {
+            None | Some(b' ' | b'\t' | b'\n' | b'\r' | b'[' | b']' | b'{' | b'}' | b',' | b':') => {
+                Ok(())
+            }
+            Some(b'/') if self.syntax == Syntax::Json5 => Ok(()),
+            Some(_) => Err(Error::TrailingCharacters),
+        }
+    }
+
+    fn peek_error(&mut self) -> Error {
+        match self.read.peek_char() {
+            Ok(Some(ch)) => Error::UnexpectedToken(ch),
+            Ok(None) => Error::UnexpectedEnd,
+            Err(err) => err,
+        }
+    }
+    fn assert_next(&mut self, expected: u8) -> Result<()> {
+        if self.read.peek()? == Some(expected) {
+            self.read.discard();
+            Ok(())
+        } else {
+            Err(self.peek_error())
+        }
+    }
+    /// Whether `b` opens a string.
+    fn is_quote(&self, b: u8) -> bool {
+        b == b'"' || (b == b'\'' && self.syntax == Syntax::Json5)
+    }
+
+    /// Whether `b` starts a number token.
+    fn is_number_start(&self, b: u8) -> bool {
+        match b {
+            b'-' | b'0'..=b'9' => true,
+            b'+' | b'.' | b'I' | b'N' => self.syntax == Syntax::Json5,
+            _ => false,
+        }
+    }
+
+    /// Whether `b` starts an unquoted object key.
+    fn is_identifier_start(&self, b: u8) -> bool {
+        self.syntax == Syntax::Json5 && (b.is_ascii_alphabetic() || b == b'_' || b == b'$')
+    }
+
+    fn parse_string(&mut self) -> Result<Reference<'de, '_>> {
+        let quote = self.peek_u8()?;
+        if !self.is_quote(quote) {
+            return Err(self.peek_error());
+        }
+        self.read.discard();
+        self.read.parse_str(
+            &mut self.scratch,
+            self.lone_surrogates,
+            self.syntax,
+            quote,
+            self.limits.string,
+        )
+    }
+
+    /// Reads an object key: a string, or an identifier under `Syntax::Json5`.
+    fn parse_key(&mut self) -> Result<Reference<'de, '_>> {
+        let b = self.peek_u8()?;
+        if !self.is_identifier_start(b) {
+            return self.parse_string();
+        }
+        let offset = self.read.offset();
+        self.scratch.clear();
+        while let Some(b) = self.read.peek()? {
+            if !is_identifier_char(b) {
                 break;
             }
-            if let Some(s) = buf.as_mut() {
-                s.push(ch)
+            if self.scratch.len() >= self.limits.string {
+                return Err(Error::StringTooLong);
             }
+            self.read.discard();
+            self.scratch.push(b);
         }
-        if let Some(buf) = buf {
-            self.0 = &self.0[at..];
-            return Ok(Cow::Owned(buf));
+        Ok(match self.read.borrow_from(offset) {
+            Some(rest) => Reference::Borrowed(&rest[..self.scratch.len()]),
+            None => Reference::Copied(self.token()),
+        })
+    }
+
+    /// Consumes an object key.
+    fn skip_key(&mut self) -> Result<()> {
+        let b = self.peek_u8()?;
+        if self.is_identifier_start(b) {
+            return self.parse_key().map(drop);
+        }
+        if !self.is_quote(b) {
+            return Err(self.peek_error());
+        }
+        self.read.discard();
+        self.read.ignore_str(self.syntax, b, self.limits.string)
+    }
+
+    /// Whether `end` follows a comma that was just consumed, which only `Syntax::Json5` allows.
+    fn trailing_comma(&mut self, end: u8) -> Result<bool> {
+        if self.syntax != Syntax::Json5 {
+            return Ok(false);
         }
-        let s = &self.0[1..at - 1];
-        self.0 = &self.0[at..];
-        Ok(Cow::Borrowed(s))
+        self.trim_start()?;
+        Ok(self.peek_u8()? == end)
     }
 
     fn parse_bool(&mut self) -> Result<bool> {
-        let bytes = self.0.as_bytes();
-        let vals: [&[u8]; 4] = [b"1", b"0", b"true", b"false"];
-        for (count, s) in vals.iter().enumerate() {
-            if bytes.starts_with(s) {
-                self.0 = &self.0[s.len()..];
-                return Ok(count & 1 == 0);
+        let numeric = self.config.bools == Bools::Numeric;
+        match self.peek_u8()? {
+            b'1' if numeric => self.read.discard(),
+            b'0' if numeric => {
+                self.read.discard();
+                return Ok(false);
+            }
+            b't' => self.skip_literal(b"true")?,
+            b'f' => {
+                self.skip_literal(b"false")?;
+                return Ok(false);
+            }
+            _ => return Err(self.peek_error()),
+        }
+        Ok(true)
+    }
+
+    /// Moves the next byte of a number into scratch space if it matches `pred`.
+    fn take_if(&mut self, pred: impl Fn(u8) -> bool) -> Result<bool> {
+        match self.read.peek()? {
+            Some(b) if pred(b) => {
+                if self.scratch.len() >= self.limits.number {
+                    return Err(Error::NumberTooLong);
+                }
+                self.read.discard();
+                self.scratch.push(b);
+                Ok(true)
             }
+            _ => Ok(false),
         }
-        Err(Error::UnexpectedToken(self.peek_char()?))
+    }
+
+    /// The token collected in scratch space.
+    fn token(&self) -> &str {
+        str::from_utf8(&self.scratch).unwrap_or_default()
     }
 
     fn parse_unsigned<T>(&mut self) -> Result<T>
     where
         T: FromStr<Err = ParseIntError>,
     {
-        let chs = self.0.chars();
-        let mut offset = 0usize;
-        for ch in chs {
-            if ch.is_ascii_digit() {
-                offset += ch.len_utf8();
-                continue;
+        self.scan_integer(false)?;
+        Ok(T::from_str(self.token())?)
+    }
+
+    fn parse_signed<T>(&mut self) -> Result<T>
+    where
+        T: FromStr<Err = ParseIntError>,
+    {
+        self.scan_integer(true)?;
+        Ok(T::from_str(self.token())?)
+    }
+
+    /// Collects an integer token into scratch space: `-? (0 | [1-9][0-9]*)`,
+    /// any run of digits and `-` under `Syntax::Permissive`, or a signed
+    /// decimal or hex integer under `Syntax::Json5`.
+    fn scan_integer(&mut self, signed: bool) -> Result<()> {
+        self.scratch.clear();
+        match self.syntax {
+            Syntax::Permissive => {
+                while self.take_if(|b| b.is_ascii_digit() || (signed && b == b'-'))? {}
+                return Ok(());
             }
-            break;
+            Syntax::Json5 => {
+                self.take_if(|b| b == b'+' || b == b'-')?;
+            }
+            Syntax::Strict if signed => {
+                self.take_if(|b| b == b'-')?;
+            }
+            Syntax::Strict => {}
         }
-        let val = T::from_str(&self.0[..offset])?;
-        self.0 = &self.0[offset..];
-        Ok(val)
+        self.scan_int_part()?;
+        Ok(())
     }
 
-    fn parse_signed<T>(&mut self) -> Result<T>
+    /// Collects `0 | [1-9][0-9]*`, or a hex integer under `Syntax::Json5`,
+    /// into scratch space. Returns whether it was hex.
+    fn scan_int_part(&mut self) -> Result<bool> {
+        if !self.take_if(|b| b == b'0')? {
+            self.scan_digits()?;
+            return Ok(false);
+        }
+        if self.syntax != Syntax::Json5 || !self.take_if(|b| b == b'x' || b == b'X')? {
+            return Ok(false);
+        }
+        self.scan_hex()?;
+        Ok(true)
+    }
+
+    /// Replaces the `0x` just collected into scratch space with the decimal
+    /// value of the hex digits that follow, so that `FromStr` can parse it.
+    fn scan_hex(&mut self) -> Result<()> {
+        let start = self.scratch.len() - 2;
+        self.scratch.truncate(start);
+        let mut value = 0u128;
+        let mut digits = 0;
+        while let Some(digit) = self.read.peek()?.and_then(|b| (b as char).to_digit(16)) {
+            if start + 2 + digits >= self.limits.number {
+                return Err(Error::NumberTooLong);
+            }
+            self.read.discard();
+            value = value
+                .checked_mul(16)
+                .and_then(|v| v.checked_add(u128::from(digit)))
+                .ok_or(Error::OutOfRange)?;
+            digits += 1;
+        }
+        if digits == 0 {
+            return Err(self.peek_error());
+        }
+        loop {
+            self.scratch.push(b'0' + (value % 10) as u8);
+            value /= 10;
+            if value == 0 {
+                break;
+            }
+        }
+        self.scratch[start..].reverse();
+        Ok(())
+    }
+
+    /// Parses a 64- or 128-bit integer, quoted unless the config says otherwise.
+    fn parse_wide_integer<T>(&mut self) -> Result<T>
     where
         T: FromStr<Err = ParseIntError>,
     {
-        let chs = self.0.chars();
-        let mut offset = 0usize;
-        for ch in chs {
-            if ch.is_ascii_digit() || ch == '-' {
-                offset += ch.len_utf8();
-                continue;
+        let quoted = match self.config.integers {
+            Integers::Quoted => true,
+            Integers::Bare => false,
+            Integers::QuoteUnsafe => {
+                let b = self.peek_u8()?;
+                self.is_quote(b)
+            }
+        };
+        if quoted {
+            let number = self.limits.number;
+            let s = self.parse_string()?;
+            if s.len() > number {
+                return Err(Error::NumberTooLong);
             }
-            break;
+            Ok(T::from_str(&s)?)
+        } else {
+            self.parse_signed()
         }
-        let val = T::from_str(&self.0[..offset])?;
-        self.0 = &self.0[offset..];
-        Ok(val)
     }
 
     fn parse_float<T>(&mut self) -> Result<T>
     where
-        T: FromStr<Err = ParseFloatError> + From<f32>,
+        T: FromStr<Err = ParseFloatError> + From<f32> + Into<f64> + Copy,
+    {
+        match (self.config.non_finite, self.peek_u8()?) {
+            (NonFinite::Null, b'n') => {
+                self.skip_literal(b"null")?;
+                return Ok(T::from(f32::NAN));
+            }
+            (NonFinite::Strings, b) if self.is_quote(b) => {
+                let v = match &*self.parse_string()? {
+                    "NaN" => f32::NAN,
+                    "Infinity" => f32::INFINITY,
+                    "-Infinity" => f32::NEG_INFINITY,
+                    other => {
+                        return Err(de::Error::invalid_value(
+                            de::Unexpected::Str(other),
+                            &"\"NaN\", \"Infinity\" or \"-Infinity\"",
+                        ))
+                    }
+                };
+                return Ok(T::from(v));
+            }
+            _ => {}
+        }
+        self.scan_number()?;
+        let v = T::from_str(self.token())?;
+        self.check_finite(v.into())?;
+        Ok(v)
+    }
+
+    /// Rejects a literal too large for its float type under `NonFinite::Error`.
+    fn check_finite(&self, v: f64) -> Result<()> {
+        if self.config.non_finite == NonFinite::Error && !v.is_finite() {
+            return Err(Error::NonFiniteFloat);
+        }
+        Ok(())
+    }
+
+    /// Visits an integer token as `u64`/`i64` and anything else as `f64`.
+    fn parse_any_number<V>(&mut self, visitor: V) -> Result<V::Value>
+    where
+        V: Visitor<'de>,
+    {
+        self.scan_number()?;
+        let number = self.token();
+        if !number.contains(['.', 'e', 'E']) {
+            if number.starts_with('-') {
+                if let Ok(val) = i64::from_str(number) {
+                    return visitor.visit_i64(val);
+                }
+            } else if let Ok(val) = u64::from_str(number) {
+                return visitor.visit_u64(val);
+            }
+        }
+        let v = f64::from_str(number)?;
+        self.check_finite(v)?;
+        visitor.visit_f64(v)
+    }
+
+    /// Visits a string holding a canonical integer that fits in 64 bits as
+    /// `u64`/`i64`, and any other string as a string.
+    fn parse_any_string<V>(&mut self, visitor: V) -> Result<V::Value>
+    where
+        V: Visitor<'de>,
     {
-        if self.0.starts_with("null") {
-            return Ok(T::from(core::f32::NAN));
+        let number = self.limits.number;
+        let s = self.parse_string()?;
+        let digits = s.strip_prefix('-').unwrap_or(&s);
+        let canonical = match digits.as_bytes() {
+            [b'0'] => true,
+            [b'1'..=b'9', rest @ ..] => rest.iter().all(u8::is_ascii_digit),
+            _ => false,
+        };
+        if canonical && s.len() > number {
+            return Err(Error::NumberTooLong);
         }
-        let chs = self.0.chars();
-        let mut offset = 0usize;
-        for ch in chs {
-            if ch.is_ascii_digit() || ch == '-' || ch == '.' {
-                offset += ch.len_utf8();
-                continue;
+        if canonical {
+            if s.starts_with('-') {
+                if let Ok(val) = i64::from_str(&s) {
+                    return visitor.visit_i64(val);
+                }
+            } else if let Ok(val) = u64::from_str(&s) {
+                return visitor.visit_u64(val);
             }
-            break;
         }
-        let val = T::from_str(&self.0[..offset])?;
-        self.0 = &self.0[offset..];
-        Ok(val)
+        match s {
+            Reference::Borrowed(s) => visitor.visit_borrowed_str(s),
+            Reference::Copied(s) => visitor.visit_str(s),
+        }
+    }
+
+    fn skip_literal(&mut self, literal: &[u8]) -> Result<()> {
+        for b in literal {
+            self.assert_next(*b)?;
+        }
+        Ok(())
+    }
+
+    /// Fails if an array, or an object if `keyed`, already holds `len` items
+    /// and another one follows.
+    fn check_len(&self, len: usize, keyed: bool) -> Result<()> {
+        match keyed {
+            false if len >= self.limits.array => Err(Error::TooManyElements),
+            true if len >= self.limits.object => Err(Error::TooManyKeys),
+            _ => Ok(()),
+        }
+    }
+
+    /// Skips the elements of an array or object whose opening bracket was already consumed.
+    fn skip_comma_separated(&mut self, end: u8, keyed: bool) -> Result<()> {
+        self.trim_start()?;
+        if self.peek_u8()? == end {
+            self.read.discard();
+            return Ok(());
+        }
+        let mut len = 0;
+        loop {
+            self.check_len(len, keyed)?;
+            len += 1;
+            if keyed {
+                self.trim_start()?;
+                self.skip_key()?;
+                self.trim_start()?;
+                self.assert_next(b':')?;
+            }
+            self.skip_value()?;
+            self.trim_start()?;
+            match self.peek_u8()? {
+                b',' => {
+                    self.read.discard();
+                    if self.trailing_comma(end)? {
+                        self.read.discard();
+                        return Ok(());
+                    }
+                }
+                b if b == end => {
+                    self.read.discard();
+                    return Ok(());
+                }
+                _ => return Err(self.peek_error()),
+            }
+        }
+    }
+
+    /// Consumes one value of any type without allocating.
+    fn skip_value(&mut self) -> Result<()> {
+        self.trim_start()?;
+        match self.peek_u8()? {
+            b if self.is_quote(b) => {
+                self.read.discard();
+                self.read.ignore_str(self.syntax, b, self.limits.string)
+            }
+            b'[' => self.nested(|des| {
+                des.read.discard();
+                des.skip_comma_separated(b']', false)
+            }),
+            b'{' => self.nested(|des| {
+                des.read.discard();
+                des.skip_comma_separated(b'}', true)
+            }),
+            b'n' => self.skip_literal(b"null"),
+            b't' => self.skip_literal(b"true"),
+            b'f' => self.skip_literal(b"false"),
+            b if self.is_number_start(b) => self.scan_number(),
+            _ => Err(self.peek_error()),
+        }
+    }
+
+    /// Collects the RFC 8259 number at the start of the input into scratch space:
+    /// `-? (0 | [1-9][0-9]*) (. [0-9]+)? ([eE] [+-]? [0-9]+)?`
+    fn scan_number(&mut self) -> Result<()> {
+        self.scratch.clear();
+        if self.syntax == Syntax::Json5 {
+            return self.scan_json5_number();
+        }
+        self.take_if(|b| b == b'-')?;
+        self.scan_int_part()?;
+        if self.take_if(|b| b == b'.')? {
+            self.scan_digits()?;
+        }
+        self.scan_exponent()
+    }
+
+    /// Collects a JSON5 number into scratch space, which may also start with
+    /// `+` or `.`, end with `.`, be hex, or be `Infinity` or `NaN`.
+    fn scan_json5_number(&mut self) -> Result<()> {
+        self.take_if(|b| b == b'+' || b == b'-')?;
+        let literal: &[u8] = match self.peek_u8()? {
+            b'I' => b"Infinity",
+            b'N' => b"NaN",
+            _ => b"",
+        };
+        if !literal.is_empty() {
+            if self.config.non_finite == NonFinite::Error {
+                return Err(Error::NonFiniteFloat);
+            }
+            self.skip_literal(literal)?;
+            self.scratch.extend_from_slice(literal);
+            return Ok(());
+        }
+        let int = self.peek_u8()? != b'.';
+        if int && self.scan_int_part()? {
+            return Ok(());
+        }
+        if self.take_if(|b| b == b'.')? {
+            if !int {
+                self.scan_digits()?;
+            }
+            while self.take_if(|b| b.is_ascii_digit())? {}
+        } else if !int {
+            return Err(self.peek_error());
+        }
+        self.scan_exponent()
+    }
+
+    /// Collects an optional `[eE] [+-]? [0-9]+` into scratch space.
+    fn scan_exponent(&mut self) -> Result<()> {
+        if self.take_if(|b| b == b'e' || b == b'E')? {
+            self.take_if(|b| b == b'+' || b == b'-')?;
+            self.scan_digits()?;
+        }
+        Ok(())
+    }
+
+    /// Collects one or more ascii digits into scratch space.
+    fn scan_digits(&mut self) -> Result<()> {
+        if !self.take_if(|b| b.is_ascii_digit())? {
+            return Err(self.peek_error());
+        }
+        while self.take_if(|b| b.is_ascii_digit())? {}
+        Ok(())
     }
 }
 
-impl<'de, 'a> de::Deserializer<'de> for &'a mut Deserializer<'de> {
+fn is_identifier_char(b: u8) -> bool {
+    b.is_ascii_alphanumeric() || b == b'_' || b == b'$'
+}
+
+impl<'de, R: Read<'de>> de::Deserializer<'de> for &mut Deserializer<R> {
     type Error = Error;
 
     fn deserialize_any<V>(self, visitor: V) -> Result<V::Value>
     where
         V: Visitor<'de>,
     {
-        self.trim_start();
-        match self.peek_u8()? {
-            b'n' => self.deserialize_unit(visitor),
-            b't' | b'f' => self.deserialize_bool(visitor),
-            b'"' => self.deserialize_str(visitor),
-            b'0'..=b'9' | b'-' => self.deserialize_f64(visitor),
-            b'[' => self.deserialize_seq(visitor),
-            b'{' => self.deserialize_map(visitor),
-            _ => Err(Error::UnexpectedToken(self.peek_char()?)),
-        }
+        self.located(|des| {
+            des.trim_start()?;
+            match des.peek_u8()? {
+                b'n' => des.deserialize_unit(visitor),
+                b't' | b'f' => des.deserialize_bool(visitor),
+                b if des.is_quote(b) && des.config.any_quoted_integers => {
+                    des.parse_any_string(visitor)
+                }
+                b if des.is_quote(b) => des.deserialize_str(visitor),
+                b if des.is_number_start(b) => des.parse_any_number(visitor),
+                b'[' => des.deserialize_seq(visitor),
+                b'{' => des.deserialize_map(visitor),
+                _ => Err(des.peek_error()),
+            }
+        })
     }
 
     fn deserialize_bool<V>(self, visitor: V) -> Result<V::Value>
     where
         V: Visitor<'de>,
     {
-        self.trim_start();
-        visitor.visit_bool(self.parse_bool()?)
+        self.located(|des| {
+            des.trim_start()?;
+            visitor.visit_bool(des.parse_bool()?)
+        })
     }
 
     fn deserialize_i8<V>(self, visitor: V) -> Result<V::Value>
     where
         V: Visitor<'de>,
     {
-        self.trim_start();
-        visitor.visit_i8(self.parse_signed()?)
+        self.located(|des| {
+            des.trim_start()?;
+            visitor.visit_i8(des.parse_signed()?)
+        })
     }
 
     fn deserialize_i16<V>(self, visitor: V) -> Result<V::Value>
     where
         V: Visitor<'de>,
     {
-        self.trim_start();
-        visitor.visit_i16(self.parse_signed()?)
+        self.located(|des| {
+            des.trim_start()?;
+            visitor.visit_i16(des.parse_signed()?)
+        })
     }
 
     fn deserialize_i32<V>(self, visitor: V) -> Result<V::Value>
     where
         V: Visitor<'de>,
     {
-        self.trim_start();
-        visitor.visit_i32(self.parse_signed()?)
+        self.located(|des| {
+            des.trim_start()?;
+            visitor.visit_i32(des.parse_signed()?)
+        })
     }
 
     fn deserialize_i64<V>(self, visitor: V) -> Result<V::Value>
     where
         V: Visitor<'de>,
     {
-        self.trim_start();
-        visitor.visit_i64(i64::from_str(&self.parse_string()?)?)
+        self.located(|des| {
+            des.trim_start()?;
+            visitor.visit_i64(des.parse_wide_integer()?)
+        })
     }
 
     fn deserialize_u8<V>(self, visitor: V) -> Result<V::Value>
     where
         V: Visitor<'de>,
     {
-        self.trim_start();
-        visitor.visit_u8(self.parse_unsigned()?)
+        self.located(|des| {
+            des.trim_start()?;
+            visitor.visit_u8(des.parse_unsigned()?)
+        })
     }
 
     fn deserialize_u16<V>(self, visitor: V) -> Result<V::Value>
     where
         V: Visitor<'de>,
     {
-        self.trim_start();
-        visitor.visit_u16(self.parse_unsigned()?)
+        self.located(|des| {
+            des.trim_start()?;
+            visitor.visit_u16(des.parse_unsigned()?)
+        })
     }
 
     fn deserialize_u32<V>(self, visitor: V) -> Result<V::Value>
     where
         V: Visitor<'de>,
     {
-        self.trim_start();
-        visitor.visit_u32(self.parse_unsigned()?)
+        self.located(|des| {
+            des.trim_start()?;
+            visitor.visit_u32(des.parse_unsigned()?)
+        })
     }
 
     fn deserialize_u64<V>(self, visitor: V) -> Result<V::Value>
     where
         V: Visitor<'de>,
     {
-        self.trim_start();
-        visitor.visit_u64(u64::from_str(&self.parse_string()?)?)
+        self.located(|des| {
+            des.trim_start()?;
+            visitor.visit_u64(des.parse_wide_integer()?)
+        })
     }
 
-    serde_if_integer128! {
-
-        fn deserialize_u128<V>(self, visitor: V) -> Result<V::Value>
-        where
-            V: Visitor<'de>,
-        {
-            self.trim_start();
-            visitor.visit_u128(u128::from_str(&self.parse_string()?)?)
-        }
-
-        fn deserialize_i128<V>(self, visitor: V) -> Result<V::Value>
-        where
-            V: Visitor<'de>,
-        {
-            self.trim_start();
-            visitor.visit_i128(i128::from_str(&self.parse_string()?)?)
-        }
+    fn deserialize_u128<V>(self, visitor: V) -> Result<V::Value>
+    where
+        V: Visitor<'de>,
+    {
+        self.located(|des| {
+            des.trim_start()?;
+            visitor.visit_u128(des.parse_wide_integer()?)
+        })
+    }
 
+    fn deserialize_i128<V>(self, visitor: V) -> Result<V::Value>
+    where
+        V: Visitor<'de>,
+    {
+        self.located(|des| {
+            des.trim_start()?;
+            visitor.visit_i128(des.parse_wide_integer()?)
+        })
     }
 
     fn deserialize_f32<V>(self, visitor: V) -> Result<V::Value>
     where
         V: Visitor<'de>,
     {
-        self.trim_start();
-        visitor.visit_f32(self.parse_float()?)
+        self.located(|des| {
+            des.trim_start()?;
+            visitor.visit_f32(des.parse_float()?)
+        })
     }
 
     fn deserialize_f64<V>(self, visitor: V) -> Result<V::Value>
     where
         V: Visitor<'de>,
     {
-        self.trim_start();
-        visitor.visit_f64(self.parse_float()?)
+        self.located(|des| {
+            des.trim_start()?;
+            visitor.visit_f64(des.parse_float()?)
+        })
     }
 
     fn deserialize_char<V>(self, visitor: V) -> Result<V::Value>
     where
         V: Visitor<'de>,
     {
-        self.trim_start();
-        let s = self.parse_string()?;
-        visitor.visit_char(s.chars().next().ok_or(Error::UnexpectedToken('\"'))?)
+        self.located(|des| {
+            des.trim_start()?;
+            let ch = des.parse_string()?.chars().next();
+            visitor.visit_char(ch.ok_or(Error::UnexpectedToken('"'))?)
+        })
     }
 
     fn deserialize_str<V>(self, visitor: V) -> Result<V::Value>
     where
         V: Visitor<'de>,
     {
-        self.trim_start();
-        match self.parse_string()? {
-            Cow::Borrowed(s) => visitor.visit_borrowed_str(s),
-            Cow::Owned(s) => visitor.visit_string(s),
-        }
+        self.located(|des| {
+            des.trim_start()?;
+            match des.parse_string()? {
+                Reference::Borrowed(s) => visitor.visit_borrowed_str(s),
+                Reference::Copied(s) => visitor.visit_str(s),
+            }
+        })
     }
 
     fn deserialize_string<V>(self, visitor: V) -> Result<V::Value>
@@ -346,45 +1047,52 @@ impl<'de, 'a> de::Deserializer<'de> for &'a mut Deserializer<'de> {
     where
         V: Visitor<'de>,
     {
-        self.trim_start();
-        let s = self.parse_string()?;
-        let b = base64::decode_config(s.as_ref(), base64::URL_SAFE)?;
-        visitor.visit_bytes(&b)
+        self.located(|des| {
+            des.trim_start()?;
+            let config = des.config;
+            let max_len = des.limits.bytes;
+            let b = config.decode_bytes(&des.parse_string()?, max_len)?;
+            visitor.visit_bytes(&b)
+        })
     }
 
     fn deserialize_byte_buf<V>(self, visitor: V) -> Result<V::Value>
     where
         V: Visitor<'de>,
     {
-        self.trim_start();
-        let s = self.parse_string()?;
-        let b = base64::decode_config(s.as_ref(), base64::URL_SAFE)?;
-        visitor.visit_byte_buf(b)
+        self.located(|des| {
+            des.trim_start()?;
+            let config = des.config;
+            let max_len = des.limits.bytes;
+            let b = config.decode_bytes(&des.parse_string()?, max_len)?;
+            visitor.visit_byte_buf(b)
+        })
     }
 
     fn deserialize_option<V>(self, visitor: V) -> Result<V::Value>
     where
         V: Visitor<'de>,
     {
-        self.trim_start();
-        if self.peek_char()? == 'n' {
-            visitor.visit_none()
-        } else {
-            visitor.visit_some(self)
-        }
+        self.located(|des| {
+            des.trim_start()?;
+            if des.read.peek()? == Some(b'n') {
+                des.skip_literal(b"null")?;
+                visitor.visit_none()
+            } else {
+                visitor.visit_some(des)
+            }
+        })
     }
 
     fn deserialize_unit<V>(self, visitor: V) -> Result<V::Value>
     where
         V: Visitor<'de>,
     {
-        self.trim_start();
-        if self.0.starts_with("null") {
-            self.0 = &self.0["null".len()..];
+        self.located(|des| {
+            des.trim_start()?;
+            des.skip_literal(b"null")?;
             visitor.visit_unit()
-        } else {
-            Err(Error::UnexpectedToken(self.peek_char()?))
-        }
+        })
     }
 
     fn deserialize_unit_struct<V>(self, _name: &'static str, visitor: V) -> Result<V::Value>
@@ -398,29 +1106,29 @@ impl<'de, 'a> de::Deserializer<'de> for &'a mut Deserializer<'de> {
     where
         V: Visitor<'de>,
     {
-        visitor.visit_newtype_struct(self)
+        self.located(|des| visitor.visit_newtype_struct(des))
     }
 
-    fn deserialize_seq<V>(mut self, visitor: V) -> Result<V::Value>
+    fn deserialize_seq<V>(self, visitor: V) -> Result<V::Value>
     where
         V: Visitor<'de>,
     {
-        self.trim_start();
-        if self.next_char()? == '[' {
-            let value = visitor.visit_seq(CommaSeparated::new(&mut self))?;
-            self.trim_start();
-            self.assert_next_char(']')?;
-            Ok(value)
-        } else {
-            Err(Error::UnexpectedToken(self.peek_char()?))
-        }
+        self.located(|des| {
+            des.trim_start()?;
+            des.nested(|des| {
+                des.assert_next(b'[')?;
+                let value = visitor.visit_seq(CommaSeparated::new(des))?;
+                des.trim_start()?;
+                des.assert_next(b']')?;
+                Ok(value)
+            })
+        })
     }
 
     fn deserialize_tuple<V>(self, _len: usize, visitor: V) -> Result<V::Value>
     where
         V: Visitor<'de>,
     {
-        self.trim_start();
         self.deserialize_seq(visitor)
     }
 
@@ -433,28 +1141,23 @@ impl<'de, 'a> de::Deserializer<'de> for &'a mut Deserializer<'de> {
     where
         V: Visitor<'de>,
     {
-        self.trim_start();
         self.deserialize_seq(visitor)
     }
 
-    fn deserialize_map<V>(mut self, visitor: V) -> Result<V::Value>
+    fn deserialize_map<V>(self, visitor: V) -> Result<V::Value>
     where
         V: Visitor<'de>,
     {
-        self.trim_start();
-        let start = self.next_char()?;
-        if start == '{' {
-            let value = visitor.visit_map(CommaSeparated::new(&mut self))?;
-            self.trim_start();
-            let end = self.next_char()?;
-            if end == '}' {
+        self.located(|des| {
+            des.trim_start()?;
+            des.nested(|des| {
+                des.assert_next(b'{')?;
+                let value = visitor.visit_map(CommaSeparated::new(des))?;
+                des.trim_start()?;
+                des.assert_next(b'}')?;
                 Ok(value)
-            } else {
-                Err(Error::UnexpectedToken(end))
-            }
-        } else {
-            Err(Error::UnexpectedToken(start))
-        }
+            })
+        })
     }
 
     fn deserialize_struct<V>(
@@ -466,7 +1169,6 @@ impl<'de, 'a> de::Deserializer<'de> for &'a mut Deserializer<'de> {
     where
         V: Visitor<'de>,
     {
-        self.trim_start();
         self.deserialize_map(visitor)
     }
 
@@ -479,23 +1181,23 @@ impl<'de, 'a> de::Deserializer<'de> for &'a mut Deserializer<'de> {
     where
         V: Visitor<'de>,
     {
-        self.trim_start();
-        let start = self.peek_char()?;
-        if start == '"' {
-            visitor.visit_enum(self.parse_string()?.into_deserializer())
-        } else if start == '{' {
-            self.next_char().unwrap();
-            let value = visitor.visit_enum(Enum::new(self))?;
-            self.trim_start();
-            let end = self.next_char()?;
-            if end == '}' {
-                Ok(value)
-            } else {
-                Err(Error::UnexpectedToken(end))
+        self.located(|des| {
+            des.trim_start()?;
+            match des.peek_u8()? {
+                b if des.is_quote(b) => match des.parse_string()? {
+                    Reference::Borrowed(s) => visitor.visit_enum(s.into_deserializer()),
+                    Reference::Copied(s) => visitor.visit_enum(s.into_deserializer()),
+                },
+                b'{' => des.nested(|des| {
+                    des.read.discard();
+                    let value = visitor.visit_enum(Enum::new(des))?;
+                    des.trim_start()?;
+                    des.assert_next(b'}')?;
+                    Ok(value)
+                }),
+                _ => Err(des.peek_error()),
             }
-        } else {
-            Err(Error::UnexpectedToken(start))
-        }
+        })
     }
 
     fn deserialize_identifier<V>(self, visitor: V) -> Result<V::Value>
@@ -509,119 +1211,402 @@ impl<'de, 'a> de::Deserializer<'de> for &'a mut Deserializer<'de> {
     where
         V: Visitor<'de>,
     {
-        visitor.visit_unit()
+        self.located(|des| {
+            des.skip_value()?;
+            visitor.visit_unit()
+        })
+    }
+}
+
+/// Yields each value in the input together with the byte offset it starts at.
+///
+/// Iteration stops after the first error, which carries the position it happened at.
+pub struct StreamDeserializer<'de, R, T> {
+    de: Deserializer<R>,
+    failed: bool,
+    lifetime: PhantomData<&'de ()>,
+    output: PhantomData<fn() -> T>,
+}
+
+impl<'de, R: Read<'de>, T> StreamDeserializer<'de, R, T> {
+    /// Returns the byte offset just past the last value yielded.
+    pub fn byte_offset(&self) -> usize {
+        self.de.read.offset()
+    }
+}
+
+impl<'de, R: Read<'de>, T: de::Deserialize<'de>> Iterator for StreamDeserializer<'de, R, T> {
+    type Item = Result<(usize, T)>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.failed {
+            return None;
+        }
+        let result = self.de.located(|des| {
+            des.trim_start()?;
+            match des.read.peek()? {
+                None => Ok(None),
+                Some(b) => {
+                    let offset = des.read.offset();
+                    let delimited = b == b'[' || b == b'{' || des.is_quote(b);
+                    let value = T::deserialize(&mut *des)?;
+                    if !delimited {
+                        des.peek_end_of_value()?;
+                    }
+                    Ok(Some((offset, value)))
+                }
+            }
+        });
+        self.failed = result.is_err();
+        result.transpose()
     }
 }
 
-struct CommaSeparated<'a, 'de: 'a> {
-    de: &'a mut Deserializer<'de>,
+/// Deserializes the elements of an array on demand, see `Deserializer::array_iter`.
+pub struct ArrayIter<'a, 'de, R, T> {
+    seq: CommaSeparated<'a, R>,
+    done: bool,
+    lifetime: PhantomData<&'de ()>,
+    output: PhantomData<fn() -> T>,
+}
+
+impl<'de, R: Read<'de>, T: de::Deserialize<'de>> Iterator for ArrayIter<'_, 'de, R, T> {
+    type Item = Result<T>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.done {
+            return None;
+        }
+        let result = match self.seq.next_element() {
+            Ok(Some(value)) => return Some(Ok(value)),
+            Ok(None) => self.seq.de.assert_next(b']'),
+            Err(err) => Err(err),
+        };
+        self.done = true;
+        result
+            .map_err(|err| self.seq.de.fix_position(err))
+            .err()
+            .map(Err)
+    }
+}
+
+struct CommaSeparated<'a, R> {
+    de: &'a mut Deserializer<R>,
     first: bool,
+    index: usize,
+    key: usize,
+    key_copy: String,
 }
 
-impl<'a, 'de> CommaSeparated<'a, 'de> {
-    fn new(de: &'a mut Deserializer<'de>) -> Self {
-        CommaSeparated { de, first: true }
+impl<'a, R> CommaSeparated<'a, R> {
+    fn new(de: &'a mut Deserializer<R>) -> Self {
+        CommaSeparated {
+            de,
+            first: true,
+            index: 0,
+            key: 0,
+            key_copy: String::new(),
+        }
     }
 }
 
-impl<'de, 'a> SeqAccess<'de> for CommaSeparated<'a, 'de> {
+impl<'de, R: Read<'de>> SeqAccess<'de> for CommaSeparated<'_, R> {
     type Error = Error;
 
     fn next_element_seed<T>(&mut self, seed: T) -> Result<Option<T::Value>>
     where
         T: DeserializeSeed<'de>,
     {
-        self.de.trim_start();
-        if self.de.peek_char()? == ']' {
+        self.de.trim_start()?;
+        if self.de.peek_u8()? == b']' {
             return Ok(None);
         }
         if !self.first {
-            self.de.assert_next_char(',')?;
+            self.de.assert_next(b',')?;
+            if self.de.trailing_comma(b']')? {
+                return Ok(None);
+            }
         }
         self.first = false;
-        seed.deserialize(&mut *self.de).map(Some)
+        let index = self.index;
+        self.de.check_len(index, false)?;
+        self.index += 1;
+        seed.deserialize(&mut *self.de)
+            .map(Some)
+            .map_err(|err| self.de.fix_path(err, Segment::Index(index)))
     }
 }
 
-impl<'de, 'a> MapAccess<'de> for CommaSeparated<'a, 'de> {
+impl<'de, R: Read<'de>> MapAccess<'de> for CommaSeparated<'_, R> {
     type Error = Error;
 
     fn next_key_seed<K>(&mut self, seed: K) -> Result<Option<K::Value>>
     where
         K: DeserializeSeed<'de>,
     {
-        self.de.trim_start();
-        let token = self.de.peek_char()?;
-        if token == '}' {
+        self.de.trim_start()?;
+        if self.de.peek_u8()? == b'}' {
             return Ok(None);
         }
         if !self.first {
-            self.de.assert_next_char(',')?;
+            self.de.assert_next(b',')?;
+            if self.de.trailing_comma(b'}')? {
+                return Ok(None);
+            }
         }
         self.first = false;
-        seed.deserialize(&mut *self.de).map(Some)
+        self.de.check_len(self.index, true)?;
+        self.index += 1;
+        self.de.trim_start()?;
+        self.key = self.de.read.offset();
+        let key = seed.deserialize(MapKey { de: &mut *self.de })?;
+        self.de.copy_key(self.key, &mut self.key_copy);
+        Ok(Some(key))
     }
 
     fn next_value_seed<V>(&mut self, seed: V) -> Result<V::Value>
     where
         V: DeserializeSeed<'de>,
     {
-        self.de.trim_start();
-        self.de.assert_next_char(':')?;
-        seed.deserialize(&mut *self.de)
+        self.de.trim_start()?;
+        self.de.assert_next(b':')?;
+        seed.deserialize(&mut *self.de).map_err(|err| {
+            let key = self.de.key_at(self.key, &self.key_copy);
+            self.de.fix_path(err, Segment::Key(key))
+        })
+    }
+}
+
+/// Reads object keys, parsing the strings written by the serializer's map-key
+/// serializer back into integers and bools.
+struct MapKey<'a, R> {
+    de: &'a mut Deserializer<R>,
+}
+
+impl<'de, R: Read<'de>> MapKey<'_, R> {
+    fn parse<T>(self) -> Result<T>
+    where
+        T: FromStr,
+        Error: From<T::Err>,
+    {
+        self.de.located(|des| {
+            des.trim_start()?;
+            Ok(T::from_str(&des.parse_key()?)?)
+        })
+    }
+
+    /// Whether the key is an identifier rather than a string.
+    fn unquoted(&mut self) -> Result<bool> {
+        self.de.located(|des| {
+            des.trim_start()?;
+            let b = des.peek_u8()?;
+            Ok(des.is_identifier_start(b))
+        })
+    }
+}
+
+macro_rules! deserialize_parsed_key {
+    ($($method:ident => $visit:ident,)*) => {
+        $(
+            fn $method<V>(self, visitor: V) -> Result<V::Value>
+            where
+                V: Visitor<'de>,
+            {
+                visitor.$visit(self.parse()?)
+            }
+        )*
+    };
+}
+
+impl<'de, R: Read<'de>> de::Deserializer<'de> for MapKey<'_, R> {
+    type Error = Error;
+
+    fn deserialize_any<V>(self, visitor: V) -> Result<V::Value>
+    where
+        V: Visitor<'de>,
+    {
+        self.deserialize_str(visitor)
+    }
+
+    deserialize_parsed_key! {
+        deserialize_i8 => visit_i8,
+        deserialize_i16 => visit_i16,
+        deserialize_i32 => visit_i32,
+        deserialize_i64 => visit_i64,
+        deserialize_i128 => visit_i128,
+        deserialize_u8 => visit_u8,
+        deserialize_u16 => visit_u16,
+        deserialize_u32 => visit_u32,
+        deserialize_u64 => visit_u64,
+        deserialize_u128 => visit_u128,
+    }
+
+    fn deserialize_bool<V>(self, visitor: V) -> Result<V::Value>
+    where
+        V: Visitor<'de>,
+    {
+        self.de.located(|des| {
+            des.trim_start()?;
+            let numeric = des.config.bools == Bools::Numeric;
+            let v = match &*des.parse_key()? {
+                "true" => true,
+                "false" => false,
+                "1" if numeric => true,
+                "0" if numeric => false,
+                other => {
+                    return Err(de::Error::invalid_value(
+                        de::Unexpected::Str(other),
+                        &"a bool key",
+                    ))
+                }
+            };
+            visitor.visit_bool(v)
+        })
+    }
+
+    fn deserialize_char<V>(self, visitor: V) -> Result<V::Value>
+    where
+        V: Visitor<'de>,
+    {
+        self.de.deserialize_char(visitor)
+    }
+
+    fn deserialize_str<V>(self, visitor: V) -> Result<V::Value>
+    where
+        V: Visitor<'de>,
+    {
+        self.de.located(|des| {
+            des.trim_start()?;
+            match des.parse_key()? {
+                Reference::Borrowed(s) => visitor.visit_borrowed_str(s),
+                Reference::Copied(s) => visitor.visit_str(s),
+            }
+        })
+    }
+
+    fn deserialize_string<V>(self, visitor: V) -> Result<V::Value>
+    where
+        V: Visitor<'de>,
+    {
+        self.deserialize_str(visitor)
+    }
+
+    fn deserialize_bytes<V>(self, visitor: V) -> Result<V::Value>
+    where
+        V: Visitor<'de>,
+    {
+        self.de.deserialize_bytes(visitor)
+    }
+
+    fn deserialize_byte_buf<V>(self, visitor: V) -> Result<V::Value>
+    where
+        V: Visitor<'de>,
+    {
+        self.de.deserialize_byte_buf(visitor)
+    }
+
+    fn deserialize_newtype_struct<V>(self, _name: &'static str, visitor: V) -> Result<V::Value>
+    where
+        V: Visitor<'de>,
+    {
+        visitor.visit_newtype_struct(self)
+    }
+
+    fn deserialize_enum<V>(
+        mut self,
+        name: &'static str,
+        variants: &'static [&'static str],
+        visitor: V,
+    ) -> Result<V::Value>
+    where
+        V: Visitor<'de>,
+    {
+        if !self.unquoted()? {
+            return self.de.deserialize_enum(name, variants, visitor);
+        }
+        self.de.located(|des| match des.parse_key()? {
+            Reference::Borrowed(s) => visitor.visit_enum(s.into_deserializer()),
+            Reference::Copied(s) => visitor.visit_enum(s.into_deserializer()),
+        })
+    }
+
+    fn deserialize_identifier<V>(self, visitor: V) -> Result<V::Value>
+    where
+        V: Visitor<'de>,
+    {
+        self.deserialize_str(visitor)
+    }
+
+    serde::forward_to_deserialize_any! {
+        f32 f64 option unit unit_struct seq tuple tuple_struct map struct ignored_any
     }
 }
 
-struct Enum<'a, 'de: 'a> {
-    de: &'a mut Deserializer<'de>,
+struct Enum<'a, R> {
+    de: &'a mut Deserializer<R>,
+    variant: usize,
+    variant_copy: String,
 }
 
-impl<'a, 'de> Enum<'a, 'de> {
-    fn new(de: &'a mut Deserializer<'de>) -> Self {
-        Enum { de }
+impl<'a, 'de, R: Read<'de>> Enum<'a, R> {
+    fn new(de: &'a mut Deserializer<R>) -> Self {
+        Enum {
+            de,
+            variant: 0,
+            variant_copy: String::new(),
+        }
+    }
+
+    fn fix_path(&self, err: Error) -> Error {
+        let variant = self.de.key_at(self.variant, &self.variant_copy);
+        self.de.fix_path(err, Segment::Variant(variant))
     }
 }
 
-impl<'de, 'a> EnumAccess<'de> for Enum<'a, 'de> {
+impl<'de, R: Read<'de>> EnumAccess<'de> for Enum<'_, R> {
     type Error = Error;
     type Variant = Self;
 
-    fn variant_seed<V>(self, seed: V) -> Result<(V::Value, Self::Variant)>
+    fn variant_seed<V>(mut self, seed: V) -> Result<(V::Value, Self::Variant)>
     where
         V: DeserializeSeed<'de>,
     {
-        let val = seed.deserialize(&mut *self.de)?;
-        self.de.trim_start();
-        self.de.assert_next_char(':')?;
+        self.de.trim_start()?;
+        self.variant = self.de.read.offset();
+        let val = seed.deserialize(MapKey { de: &mut *self.de })?;
+        self.de.copy_key(self.variant, &mut self.variant_copy);
+        self.de.trim_start()?;
+        self.de.assert_next(b':')?;
         Ok((val, self))
     }
 }
 
-impl<'de, 'a> VariantAccess<'de> for Enum<'a, 'de> {
+impl<'de, R: Read<'de>> VariantAccess<'de> for Enum<'_, R> {
     type Error = Error;
 
     fn unit_variant(self) -> Result<()> {
-        Err(Error::UnexpectedToken(self.de.peek_char()?))
+        Err(self.de.peek_error())
     }
 
     fn newtype_variant_seed<T>(self, seed: T) -> Result<T::Value>
     where
         T: DeserializeSeed<'de>,
     {
-        seed.deserialize(self.de)
+        seed.deserialize(&mut *self.de)
+            .map_err(|err| self.fix_path(err))
     }
 
     fn tuple_variant<V>(self, _len: usize, visitor: V) -> Result<V::Value>
     where
         V: Visitor<'de>,
     {
-        de::Deserializer::deserialize_seq(self.de, visitor)
+        de::Deserializer::deserialize_seq(&mut *self.de, visitor).map_err(|err| self.fix_path(err))
     }
 
     fn struct_variant<V>(self, _fields: &'static [&'static str], visitor: V) -> Result<V::Value>
     where
         V: Visitor<'de>,
     {
-        de::Deserializer::deserialize_map(self.de, visitor)
+        de::Deserializer::deserialize_map(&mut *self.de, visitor).map_err(|err| self.fix_path(err))
     }
 }
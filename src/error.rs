@@ -1,5 +1,7 @@
+use alloc::boxed::Box;
 use alloc::string::String;
 use alloc::string::ToString;
+use alloc::vec::Vec;
 use base64::DecodeError;
 use core::fmt::Display;
 use core::num::ParseFloatError;
@@ -7,18 +9,120 @@ use core::num::ParseIntError;
 use core::str::Utf8Error;
 use serde::de::StdError;
 
+/// A location in the input, as a byte offset and a 1-based line and column.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Position {
+    pub offset: usize,
+    pub line: usize,
+    pub column: usize,
+}
+
+/// One step from a parent value into a nested one.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Segment {
+    Key(String),
+    Index(usize),
+    Variant(String),
+}
+
+/// The keys, indices and variants leading from the top-level value to where an error happened.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Path(pub Vec<Segment>);
+
+impl Display for Path {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        for (i, segment) in self.0.iter().enumerate() {
+            match segment {
+                Segment::Index(index) => write!(f, "[{}]", index)?,
+                Segment::Key(name) | Segment::Variant(name) => {
+                    if i > 0 {
+                        write!(f, ".")?;
+                    }
+                    write!(f, "{}", name)?;
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
 #[derive(Debug)]
 pub enum JsonError {
     UnexpectedEnd,
     InvalidUnicodeEscapeSequence,
     UnexpectedUnicodeEscapeSequence(u32),
+    LoneSurrogate(u32),
     UnexpectedToken(char),
+    TrailingCharacters,
     OutOfRange,
+    /// More arrays and objects were open at once than the depth limit allows.
+    RecursionLimitExceeded,
+    /// A string longer than `Deserializer::max_string_len`.
+    StringTooLong,
+    /// An array with more elements than `Deserializer::max_array_len`.
+    TooManyElements,
+    /// An object with more keys than `Deserializer::max_object_len`.
+    TooManyKeys,
+    /// A number longer than `Deserializer::max_number_len`.
+    NumberTooLong,
+    /// A byte array longer than `Deserializer::max_bytes_len` once decoded.
+    BytesTooLong,
+    /// A NaN or infinite float under `NonFinite::Error`.
+    NonFiniteFloat,
     ParseFloatError(ParseFloatError),
     ParseIntError(ParseIntError),
     Base64Error(DecodeError),
+    /// A byte array under `ByteEncoding::Hex` that is not an even number of hex digits.
+    InvalidHex,
     Utf8Error(Utf8Error),
     Custom(String),
+    #[cfg(feature = "std")]
+    Io(std::io::Error),
+    Fmt,
+    /// Wraps an error raised while deserializing with where in the input it happened.
+    At {
+        error: Box<JsonError>,
+        position: Position,
+        path: Path,
+    },
+}
+
+impl JsonError {
+    /// Returns the underlying error, without its position.
+    pub fn kind(&self) -> &JsonError {
+        match self {
+            JsonError::At { error, .. } => error.kind(),
+            other => other,
+        }
+    }
+
+    pub fn position(&self) -> Option<Position> {
+        match self {
+            JsonError::At { position, .. } => Some(*position),
+            _ => None,
+        }
+    }
+
+    /// Byte offset into the input.
+    pub fn offset(&self) -> Option<usize> {
+        self.position().map(|p| p.offset)
+    }
+
+    pub fn line(&self) -> Option<usize> {
+        self.position().map(|p| p.line)
+    }
+
+    pub fn column(&self) -> Option<usize> {
+        self.position().map(|p| p.column)
+    }
+
+    /// Returns the path to the value that failed to deserialize.
+    pub fn path(&self) -> Option<&Path> {
+        match self {
+            JsonError::At { path, .. } => Some(path),
+            _ => None,
+        }
+    }
 }
 
 impl Display for JsonError {
@@ -29,13 +133,43 @@ impl Display for JsonError {
             JsonError::UnexpectedUnicodeEscapeSequence(h) => {
                 write!(f, "Unexpected Unicode escape sequence {:#08X}", h)
             }
+            JsonError::LoneSurrogate(code) => {
+                write!(f, "Lone UTF-16 surrogate \\u{:04X} in string", code)
+            }
             JsonError::UnexpectedToken(token) => write!(f, "Unexpected token {}", token),
+            JsonError::TrailingCharacters => write!(f, "Trailing characters"),
             JsonError::OutOfRange => write!(f, "out of range"),
+            JsonError::RecursionLimitExceeded => write!(f, "recursion limit exceeded"),
+            JsonError::StringTooLong => write!(f, "string too long"),
+            JsonError::TooManyElements => write!(f, "too many array elements"),
+            JsonError::TooManyKeys => write!(f, "too many object keys"),
+            JsonError::NumberTooLong => write!(f, "number too long"),
+            JsonError::BytesTooLong => write!(f, "byte array too long"),
+            JsonError::NonFiniteFloat => write!(f, "NaN or infinite float"),
             JsonError::ParseFloatError(e) => write!(f, "parse float error : {}", e),
             JsonError::ParseIntError(e) => write!(f, "parse int error : {}", e),
             JsonError::Base64Error(e) => write!(f, "base64 decode error : {}", e),
+            JsonError::InvalidHex => write!(f, "invalid hex string"),
             JsonError::Utf8Error(e) => write!(f, "Utf8 error : {}", e),
             JsonError::Custom(e) => write!(f, "custom error : {}", e),
+            #[cfg(feature = "std")]
+            JsonError::Io(e) => write!(f, "io error : {}", e),
+            JsonError::Fmt => write!(f, "formatter error"),
+            JsonError::At {
+                error,
+                position,
+                path,
+            } => {
+                write!(
+                    f,
+                    "{} at line {} column {} (offset {})",
+                    error, position.line, position.column, position.offset
+                )?;
+                if !path.0.is_empty() {
+                    write!(f, ", path {}", path)?;
+                }
+                Ok(())
+            }
         }
     }
 }
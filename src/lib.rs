@@ -1,7 +1,25 @@
 #![no_std]
 extern crate alloc;
+#[cfg(feature = "std")]
+extern crate std;
+#[macro_use]
+mod macros;
+pub mod config;
 pub mod de;
 mod error;
+pub mod push;
+pub mod read;
 pub mod ser;
+#[cfg(feature = "futures")]
+pub mod stream;
+pub mod value;
 
-pub use error::{JsonError as Error, Result};
+pub use config::Config;
+pub use error::{JsonError as Error, Path, Position, Result, Segment};
+pub use value::{from_value, to_value, Map, Number, Value};
+
+#[doc(hidden)]
+pub mod __private {
+    pub use alloc::string::String;
+    pub use alloc::vec::Vec;
+}
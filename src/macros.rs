@@ -0,0 +1,70 @@
+/// Builds a `json4web::Value` from JSON-like syntax.
+///
+/// Any other expression is converted with `to_value`, so `json!(5u64)` gives the
+/// quoted `Value::String("5")` like the serializer would.
+///
+/// ```
+/// let value = json4web::json!({
+///     "id": 7,
+///     "tags": ["a", "b"],
+///     "parent": null,
+///     "ok": true,
+/// });
+/// assert_eq!(value["tags"][1], "b");
+/// ```
+#[macro_export]
+macro_rules! json {
+    (null) => {
+        $crate::Value::Null
+    };
+    ([]) => {
+        $crate::Value::Array($crate::__private::Vec::new())
+    };
+    ([ $($tt:tt)+ ]) => {{
+        let mut array = $crate::__private::Vec::new();
+        $crate::json_internal!(@array array () $($tt)+);
+        $crate::Value::Array(array)
+    }};
+    ({}) => {
+        $crate::Value::Object($crate::Map::new())
+    };
+    ({ $($tt:tt)+ }) => {{
+        let mut object = $crate::Map::new();
+        $crate::json_internal!(@object object $($tt)+);
+        $crate::Value::Object(object)
+    }};
+    ($other:expr) => {
+        $crate::to_value(&$other).unwrap()
+    };
+}
+
+#[macro_export]
+#[doc(hidden)]
+macro_rules! json_internal {
+    (@array $array:ident ()) => {};
+    (@array $array:ident ($($elem:tt)+)) => {
+        $array.push($crate::json!($($elem)+));
+    };
+    (@array $array:ident ($($elem:tt)*) , $($rest:tt)*) => {
+        $crate::json_internal!(@array $array ($($elem)*));
+        $crate::json_internal!(@array $array () $($rest)*);
+    };
+    (@array $array:ident ($($elem:tt)*) $next:tt $($rest:tt)*) => {
+        $crate::json_internal!(@array $array ($($elem)* $next) $($rest)*);
+    };
+
+    (@object $object:ident) => {};
+    (@object $object:ident $key:tt : $($rest:tt)+) => {
+        $crate::json_internal!(@member $object ($key) () $($rest)+);
+    };
+    (@member $object:ident ($key:tt) ($($value:tt)+)) => {
+        $object.insert($crate::__private::String::from($key), $crate::json!($($value)+));
+    };
+    (@member $object:ident ($key:tt) ($($value:tt)+) , $($rest:tt)*) => {
+        $crate::json_internal!(@member $object ($key) ($($value)+));
+        $crate::json_internal!(@object $object $($rest)*);
+    };
+    (@member $object:ident ($key:tt) ($($value:tt)*) $next:tt $($rest:tt)*) => {
+        $crate::json_internal!(@member $object ($key) ($($value)* $next) $($rest)*);
+    };
+}
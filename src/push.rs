@@ -0,0 +1,328 @@
+//! A resumable parser for input that arrives in chunks.
+//!
+//! `PushParser` only tracks nesting, strings and escapes while bytes come in,
+//! so chunk boundaries may fall anywhere, even inside a string, a number or a
+//! UTF-8 sequence. Each completed value is then deserialized with `de::from_slice`.
+
+use alloc::boxed::Box;
+use alloc::vec::Vec;
+use serde::de::DeserializeOwned;
+
+use crate::{de, Config, Error, Path, Position, Result, Segment};
+
+/// Where a `PushParser` in array mode is relative to the top-level array.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Array {
+    /// Waiting for the opening `[`.
+    Before,
+    /// Inside the array. `expect_value` is set after `[` and `,`.
+    Inside { first: bool, expect_value: bool },
+    /// Past the closing `]`.
+    After,
+}
+
+/// Splits a stream of chunks into top-level values, or into the elements of a
+/// top-level array, and deserializes each one as soon as it is complete.
+///
+/// ```
+/// use json4web::push::PushParser;
+///
+/// let mut parser = PushParser::array_elements();
+/// parser.feed(br#"[{"id":1},{"i"#);
+/// assert_eq!(parser.next_value::<json4web::Value>().unwrap().unwrap()["id"], 1u64);
+/// assert!(parser.next_value::<json4web::Value>().is_none());
+/// parser.feed(br#"d":2}]"#);
+/// parser.finish();
+/// assert_eq!(parser.next_value::<json4web::Value>().unwrap().unwrap()["id"], 2u64);
+/// assert!(parser.next_value::<json4web::Value>().is_none());
+/// ```
+pub struct PushParser {
+    buf: Vec<u8>,
+    /// Bytes of `buf` that belong to values already returned. They are only
+    /// dropped when the next chunk comes in, so `buf` isn't shifted per value.
+    consumed: usize,
+    /// Bytes of `buf` that were already scanned.
+    scanned: usize,
+    /// Position of `buf[scanned]` in the whole stream.
+    position: Position,
+    /// Start of the value being scanned, as an index into `buf` and a stream position.
+    start: Option<(usize, Position)>,
+    /// Closing brackets of the open arrays and objects in the current value.
+    closers: Vec<u8>,
+    in_string: bool,
+    escaped: bool,
+    /// Whether the current value is a number or a literal, which only ends at a delimiter.
+    scalar: bool,
+    array: Option<Array>,
+    index: usize,
+    finished: bool,
+    failed: bool,
+    config: Config,
+}
+
+impl Default for PushParser {
+    fn default() -> Self {
+        PushParser::new()
+    }
+}
+
+impl PushParser {
+    /// A parser that yields whitespace-separated top-level values.
+    pub fn new() -> Self {
+        PushParser {
+            buf: Vec::new(),
+            consumed: 0,
+            scanned: 0,
+            position: Position {
+                offset: 0,
+                line: 1,
+                column: 1,
+            },
+            start: None,
+            closers: Vec::new(),
+            in_string: false,
+            escaped: false,
+            scalar: false,
+            array: None,
+            index: 0,
+            finished: false,
+            failed: false,
+            config: Config::json4web(),
+        }
+    }
+
+    /// A parser that expects a single top-level array and yields its elements.
+    pub fn array_elements() -> Self {
+        PushParser {
+            array: Some(Array::Before),
+            ..PushParser::new()
+        }
+    }
+
+    /// Deserializes values following the wire conventions of `config`.
+    pub fn with_config(mut self, config: Config) -> Self {
+        self.config = config;
+        self
+    }
+
+    /// Appends the next chunk of input. Does nothing once the parser has failed.
+    pub fn feed(&mut self, chunk: &[u8]) {
+        if self.failed {
+            return;
+        }
+        self.buf.drain(..self.consumed);
+        self.scanned -= self.consumed;
+        if let Some((start, _)) = &mut self.start {
+            *start -= self.consumed;
+        }
+        self.consumed = 0;
+        self.buf.extend_from_slice(chunk);
+    }
+
+    /// Signals that no more chunks will be fed, so a trailing number or literal
+    /// is complete and anything left open is an error.
+    pub fn finish(&mut self) {
+        self.finished = true;
+    }
+
+    /// Whether malformed input stopped the parser for good, see `next_value`.
+    pub fn is_failed(&self) -> bool {
+        self.failed
+    }
+
+    /// Returns the next completed value, or `None` if more input is needed.
+    ///
+    /// A value that fails to deserialize is skipped after its error is returned.
+    /// Malformed nesting stops the parser for good.
+    pub fn next_value<T>(&mut self) -> Option<Result<T>>
+    where
+        T: DeserializeOwned,
+    {
+        if self.failed {
+            return None;
+        }
+        match self.scan() {
+            Ok(Some((end, start, position))) => {
+                let result = de::from_slice_with_config(&self.buf[start..end], self.config)
+                    .map_err(|err| self.relocate(err, position));
+                self.consumed = end;
+                self.index += 1;
+                Some(result)
+            }
+            Ok(None) => None,
+            Err(err) => {
+                self.failed = true;
+                Some(Err(Error::At {
+                    error: Box::new(err),
+                    position: self.position,
+                    path: Path::default(),
+                }))
+            }
+        }
+    }
+
+    /// Moves an error from a value starting at `start` to its place in the stream.
+    fn relocate(&self, err: Error, start: Position) -> Error {
+        match err {
+            Error::At {
+                error,
+                mut position,
+                mut path,
+            } => {
+                if position.line == 1 {
+                    position.column += start.column - 1;
+                }
+                position.line += start.line - 1;
+                position.offset += start.offset;
+                if self.array.is_some() {
+                    path.0.insert(0, Segment::Index(self.index));
+                }
+                Error::At {
+                    error,
+                    position,
+                    path,
+                }
+            }
+            err => err,
+        }
+    }
+
+    fn advance(&mut self) {
+        let b = self.buf[self.scanned];
+        self.scanned += 1;
+        self.position.offset += 1;
+        if b == b'\n' {
+            self.position.line += 1;
+            self.position.column = 1;
+        } else if b & 0xC0 != 0x80 {
+            self.position.column += 1;
+        }
+    }
+
+    /// Scans up to the end of the next complete value and returns that end
+    /// along with where the value starts.
+    fn scan(&mut self) -> Result<Option<(usize, usize, Position)>> {
+        while self.scanned < self.buf.len() {
+            let b = self.buf[self.scanned];
+            if let Some((start, position)) = self.start {
+                if self.scalar && is_delimiter(b) {
+                    return Ok(Some(self.complete(self.scanned, start, position)));
+                }
+                if self.scalar && b == b'"' {
+                    return Err(Error::UnexpectedToken('"'));
+                }
+                if self.in_string {
+                    if self.escaped {
+                        self.escaped = false;
+                    } else if b == b'\\' {
+                        self.escaped = true;
+                    } else if b == b'"' {
+                        self.in_string = false;
+                    }
+                } else {
+                    match b {
+                        b'"' => self.in_string = true,
+                        b'[' => self.closers.push(b']'),
+                        b'{' => self.closers.push(b'}'),
+                        b']' | b'}' if self.closers.pop() != Some(b) => {
+                            return Err(Error::UnexpectedToken(b as char));
+                        }
+                        _ => {}
+                    }
+                }
+                self.advance();
+                if !self.scalar && !self.in_string && self.closers.is_empty() {
+                    return Ok(Some(self.complete(self.scanned, start, position)));
+                }
+                continue;
+            }
+            if is_whitespace(b) {
+                self.advance();
+                continue;
+            }
+            match self.array {
+                None => {}
+                Some(Array::Before) if b == b'[' => {
+                    self.array = Some(Array::Inside {
+                        first: true,
+                        expect_value: true,
+                    });
+                    self.advance();
+                    continue;
+                }
+                Some(Array::Inside {
+                    first,
+                    expect_value,
+                }) => match b {
+                    b']' if first || !expect_value => {
+                        self.array = Some(Array::After);
+                        self.advance();
+                        continue;
+                    }
+                    b',' if !expect_value => {
+                        self.array = Some(Array::Inside {
+                            first: false,
+                            expect_value: true,
+                        });
+                        self.advance();
+                        continue;
+                    }
+                    _ if expect_value => {}
+                    _ => return Err(Error::UnexpectedToken(b as char)),
+                },
+                Some(Array::Before) => return Err(Error::UnexpectedToken(b as char)),
+                Some(Array::After) => return Err(Error::TrailingCharacters),
+            }
+            self.start = Some((self.scanned, self.position));
+            match b {
+                b'"' => self.in_string = true,
+                b'[' => self.closers.push(b']'),
+                b'{' => self.closers.push(b'}'),
+                b']' | b'}' | b',' | b':' => return Err(Error::UnexpectedToken(b as char)),
+                _ => self.scalar = true,
+            }
+            self.advance();
+        }
+        if let Some((start, position)) = self.start {
+            if self.finished && self.scalar {
+                return Ok(Some(self.complete(self.scanned, start, position)));
+            }
+        } else {
+            self.buf.drain(..self.scanned);
+            self.scanned = 0;
+            self.consumed = 0;
+        }
+        let open = self.start.is_some()
+            || matches!(self.array, Some(Array::Before) | Some(Array::Inside { .. }));
+        if self.finished && open {
+            return Err(Error::UnexpectedEnd);
+        }
+        Ok(None)
+    }
+
+    fn complete(
+        &mut self,
+        end: usize,
+        start: usize,
+        position: Position,
+    ) -> (usize, usize, Position) {
+        self.start = None;
+        self.scalar = false;
+        if let Some(Array::Inside { .. }) = self.array {
+            self.array = Some(Array::Inside {
+                first: false,
+                expect_value: false,
+            });
+        }
+        (end, start, position)
+    }
+}
+
+fn is_whitespace(b: u8) -> bool {
+    matches!(b, b' ' | b'\t' | b'\n' | b'\r')
+}
+
+/// What may end a number or literal: whitespace or a structural character.
+fn is_delimiter(b: u8) -> bool {
+    is_whitespace(b) || matches!(b, b',' | b']' | b'}' | b'[' | b'{' | b':')
+}
@@ -0,0 +1,475 @@
+//! Input sources for `de::Deserializer`.
+//!
+//! `StrRead` borrows strings straight out of the input where it can;
+//! `IoRead` pulls bytes from a `std::io::Read` and always decodes strings
+//! into a scratch buffer instead.
+
+use crate::de::{LoneSurrogates, Syntax};
+use crate::{Error, Position, Result};
+use alloc::vec::Vec;
+use core::ops::Deref;
+use core::str;
+
+/// A string read from the input: either borrowed for `'de` or copied into scratch space.
+pub enum Reference<'b, 'c> {
+    Borrowed(&'b str),
+    Copied(&'c str),
+}
+
+impl Deref for Reference<'_, '_> {
+    type Target = str;
+
+    fn deref(&self) -> &str {
+        match *self {
+            Reference::Borrowed(s) => s,
+            Reference::Copied(s) => s,
+        }
+    }
+}
+
+mod private {
+    pub trait Sealed {}
+}
+
+/// A source of JSON bytes. This trait is sealed; use `StrRead` or `IoRead`.
+pub trait Read<'de>: private::Sealed {
+    /// Returns the next byte without consuming it.
+    fn peek(&mut self) -> Result<Option<u8>>;
+
+    /// Returns the next character without consuming it.
+    fn peek_char(&mut self) -> Result<Option<char>>;
+
+    /// Consumes the byte returned by the last `peek`.
+    fn discard(&mut self);
+
+    fn next(&mut self) -> Result<Option<u8>> {
+        let next = self.peek()?;
+        if next.is_some() {
+            self.discard();
+        }
+        Ok(next)
+    }
+
+    /// Returns the byte offset of the next unread byte.
+    fn offset(&self) -> usize;
+
+    /// Returns the position of the next unread byte.
+    fn position(&self) -> Position;
+
+    /// Returns the input from byte offset `offset` on, if it is still available.
+    fn borrow_from(&self, offset: usize) -> Option<&'de str>;
+
+    /// Decodes the rest of a string whose opening `quote` was already consumed.
+    /// Fails once more than `max_len` bytes of the input lie between the quotes.
+    fn parse_str<'s>(
+        &'s mut self,
+        scratch: &'s mut Vec<u8>,
+        lone_surrogates: LoneSurrogates,
+        syntax: Syntax,
+        quote: u8,
+        max_len: usize,
+    ) -> Result<Reference<'de, 's>>;
+
+    /// Skips the rest of a string whose opening `quote` was already consumed, see `parse_str`.
+    fn ignore_str(&mut self, syntax: Syntax, quote: u8, max_len: usize) -> Result<()>
+    where
+        Self: Sized,
+    {
+        let checked = syntax != Syntax::Permissive;
+        let begin = self.offset();
+        loop {
+            let b = self.peek()?.ok_or(Error::UnexpectedEnd)?;
+            if checked && b < 0x20 {
+                return Err(Error::UnexpectedToken(b as char));
+            }
+            // Characters are consumed whole below, so this stops on a char boundary.
+            if b != quote && self.offset() - begin >= max_len {
+                return Err(Error::StringTooLong);
+            }
+            self.discard();
+            match b {
+                b if b == quote => return Ok(()),
+                b'\\' if checked => match self.next()?.ok_or(Error::UnexpectedEnd)? {
+                    b'u' => {
+                        parse_hex4(self)?;
+                    }
+                    escape if simple_escape(escape, syntax).is_some() => {}
+                    token => return Err(Error::UnexpectedToken(finish_char(self, token)?)),
+                },
+                b'\\' => {
+                    let escape = self.next()?.ok_or(Error::UnexpectedEnd)?;
+                    finish_char(self, escape)?;
+                }
+                b if b >= 0x80 => {
+                    finish_char(self, b)?;
+                }
+                _ => {}
+            }
+        }
+    }
+}
+
+/// Reads from a `&str`, borrowing every string that contains no escapes.
+pub struct StrRead<'a> {
+    input: &'a str,
+    index: usize,
+}
+
+impl<'a> StrRead<'a> {
+    pub fn new(input: &'a str) -> Self {
+        StrRead { input, index: 0 }
+    }
+
+    /// A reader that has already consumed all of `input`.
+    pub(crate) fn at_end(input: &'a str) -> Self {
+        StrRead {
+            input,
+            index: input.len(),
+        }
+    }
+}
+
+impl private::Sealed for StrRead<'_> {}
+
+impl<'a> Read<'a> for StrRead<'a> {
+    fn peek(&mut self) -> Result<Option<u8>> {
+        Ok(self.input.as_bytes().get(self.index).copied())
+    }
+
+    fn peek_char(&mut self) -> Result<Option<char>> {
+        Ok(match self.input.get(self.index..) {
+            Some(rest) => rest.chars().next(),
+            None => Some(char::REPLACEMENT_CHARACTER),
+        })
+    }
+
+    fn discard(&mut self) {
+        self.index += 1;
+    }
+
+    fn offset(&self) -> usize {
+        self.index
+    }
+
+    fn position(&self) -> Position {
+        let mut end = self.index;
+        while !self.input.is_char_boundary(end) {
+            end -= 1;
+        }
+        let consumed = &self.input[..end];
+        let line_start = consumed.rfind('\n').map_or(0, |at| at + 1);
+        Position {
+            offset: self.index,
+            line: consumed.matches('\n').count() + 1,
+            column: consumed[line_start..].chars().count() + 1,
+        }
+    }
+
+    fn borrow_from(&self, offset: usize) -> Option<&'a str> {
+        self.input.get(offset..)
+    }
+
+    fn parse_str<'s>(
+        &'s mut self,
+        scratch: &'s mut Vec<u8>,
+        lone_surrogates: LoneSurrogates,
+        syntax: Syntax,
+        quote: u8,
+        max_len: usize,
+    ) -> Result<Reference<'a, 's>> {
+        let bytes = self.input.as_bytes();
+        let checked = syntax != Syntax::Permissive;
+        let begin = self.index;
+        let mut start = self.index;
+        scratch.clear();
+        loop {
+            let at = bytes[self.index..]
+                .iter()
+                .position(|b| *b == quote || *b == b'\\' || (checked && *b < 0x20))
+                .map(|at| self.index + at);
+            let at = match at {
+                Some(at) => at,
+                None => {
+                    self.index = bytes.len();
+                    return Err(Error::UnexpectedEnd);
+                }
+            };
+            if at - begin > max_len {
+                self.index = (begin + max_len..at)
+                    .find(|i| self.input.is_char_boundary(*i))
+                    .unwrap_or(at);
+                return Err(Error::StringTooLong);
+            }
+            if bytes[at] < 0x20 {
+                self.index = at;
+                return Err(Error::UnexpectedToken(bytes[at] as char));
+            }
+            self.index = at + 1;
+            if bytes[at] == quote {
+                if scratch.is_empty() {
+                    return Ok(Reference::Borrowed(&self.input[start..at]));
+                }
+                scratch.extend_from_slice(&bytes[start..at]);
+                // Only whole chars from the input and escapes decoded by
+                // `parse_escape` were pushed.
+                return Ok(Reference::Copied(unsafe {
+                    str::from_utf8_unchecked(scratch)
+                }));
+            }
+            scratch.extend_from_slice(&bytes[start..at]);
+            parse_escape(self, scratch, lone_surrogates, syntax)?;
+            start = self.index;
+        }
+    }
+}
+
+/// Reads from a `std::io::Read`, decoding every string into scratch space.
+#[cfg(feature = "std")]
+pub struct IoRead<R> {
+    reader: R,
+    buf: Vec<u8>,
+    at: usize,
+    offset: usize,
+    line: usize,
+    column: usize,
+}
+
+#[cfg(feature = "std")]
+impl<R: std::io::Read> IoRead<R> {
+    pub fn new(reader: R) -> Self {
+        IoRead {
+            reader,
+            buf: Vec::new(),
+            at: 0,
+            offset: 0,
+            line: 1,
+            column: 1,
+        }
+    }
+
+    /// Buffers at least `n` unread bytes unless the reader runs out first.
+    fn fill(&mut self, n: usize) -> Result<()> {
+        if self.buf.len() - self.at >= n {
+            return Ok(());
+        }
+        self.buf.drain(..self.at);
+        self.at = 0;
+        let mut chunk = [0; 8192];
+        while self.buf.len() < n {
+            match self.reader.read(&mut chunk) {
+                Ok(0) => break,
+                Ok(len) => self.buf.extend_from_slice(&chunk[..len]),
+                Err(err) if err.kind() == std::io::ErrorKind::Interrupted => {}
+                Err(err) => return Err(Error::Io(err)),
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(feature = "std")]
+impl<R> private::Sealed for IoRead<R> {}
+
+#[cfg(feature = "std")]
+impl<'de, R: std::io::Read> Read<'de> for IoRead<R> {
+    fn peek(&mut self) -> Result<Option<u8>> {
+        self.fill(1)?;
+        Ok(self.buf.get(self.at).copied())
+    }
+
+    fn peek_char(&mut self) -> Result<Option<char>> {
+        self.fill(4)?;
+        let ahead = &self.buf[self.at..self.buf.len().min(self.at + 4)];
+        let valid = match str::from_utf8(ahead) {
+            Ok(valid) => valid,
+            Err(err) => str::from_utf8(&ahead[..err.valid_up_to()]).unwrap_or_default(),
+        };
+        Ok(valid
+            .chars()
+            .next()
+            .or_else(|| ahead.first().map(|_| char::REPLACEMENT_CHARACTER)))
+    }
+
+    fn discard(&mut self) {
+        let b = self.buf[self.at];
+        self.at += 1;
+        self.offset += 1;
+        if b == b'\n' {
+            self.line += 1;
+            self.column = 1;
+        } else if b & 0xC0 != 0x80 {
+            self.column += 1;
+        }
+    }
+
+    fn offset(&self) -> usize {
+        self.offset
+    }
+
+    fn position(&self) -> Position {
+        Position {
+            offset: self.offset,
+            line: self.line,
+            column: self.column,
+        }
+    }
+
+    fn borrow_from(&self, _offset: usize) -> Option<&'de str> {
+        None
+    }
+
+    fn parse_str<'s>(
+        &'s mut self,
+        scratch: &'s mut Vec<u8>,
+        lone_surrogates: LoneSurrogates,
+        syntax: Syntax,
+        quote: u8,
+        max_len: usize,
+    ) -> Result<Reference<'de, 's>> {
+        let checked = syntax != Syntax::Permissive;
+        let begin = self.offset;
+        scratch.clear();
+        loop {
+            let b = self.peek()?.ok_or(Error::UnexpectedEnd)?;
+            if checked && b < 0x20 {
+                return Err(Error::UnexpectedToken(b as char));
+            }
+            if b != quote && self.offset - begin >= max_len {
+                return Err(Error::StringTooLong);
+            }
+            self.discard();
+            match b {
+                b if b == quote => break,
+                b'\\' => parse_escape(self, scratch, lone_surrogates, syntax)?,
+                b => scratch.push(b),
+            }
+        }
+        Ok(Reference::Copied(str::from_utf8(scratch)?))
+    }
+}
+
+fn parse_hex4<'de, R: Read<'de>>(read: &mut R) -> Result<u32> {
+    let mut code = 0u32;
+    for _ in 0..4 {
+        let b = read.peek()?.ok_or(Error::UnexpectedEnd)?;
+        let digit = (b as char)
+            .to_digit(16)
+            .ok_or(Error::InvalidUnicodeEscapeSequence)?;
+        read.discard();
+        code = (code << 4) + digit;
+    }
+    Ok(code)
+}
+
+/// Consumes the rest of the UTF-8 sequence that starts with the already
+/// consumed byte `first`, so the reader never stops inside a character.
+fn finish_char<'de, R: Read<'de>>(read: &mut R, first: u8) -> Result<char> {
+    let width = match first {
+        0xC0..=0xDF => 2,
+        0xE0..=0xEF => 3,
+        0xF0..=0xF7 => 4,
+        _ => 1,
+    };
+    let mut buf = [first, 0, 0, 0];
+    let mut len = 1;
+    while len < width {
+        match read.peek()? {
+            Some(b) if b & 0xC0 == 0x80 => {
+                read.discard();
+                buf[len] = b;
+                len += 1;
+            }
+            _ => break,
+        }
+    }
+    let ch = str::from_utf8(&buf[..len])?.chars().next();
+    Ok(ch.unwrap_or(char::REPLACEMENT_CHARACTER))
+}
+
+fn push_char(scratch: &mut Vec<u8>, ch: char) {
+    scratch.extend_from_slice(ch.encode_utf8(&mut [0; 4]).as_bytes());
+}
+
+fn push_lone(scratch: &mut Vec<u8>, code: u32, lone_surrogates: LoneSurrogates) -> Result<()> {
+    match lone_surrogates {
+        LoneSurrogates::Reject => Err(Error::LoneSurrogate(code)),
+        LoneSurrogates::Replace => {
+            push_char(scratch, char::REPLACEMENT_CHARACTER);
+            Ok(())
+        }
+    }
+}
+
+/// Decodes the character after a backslash, other than `u`.
+fn simple_escape(escape: u8, syntax: Syntax) -> Option<u8> {
+    Some(match escape {
+        b'"' | b'\\' | b'/' => escape,
+        b'\'' if syntax == Syntax::Json5 => escape,
+        b'b' => b'\x08',
+        b'f' => b'\x0c',
+        b'n' => b'\n',
+        b'r' => b'\r',
+        b't' => b'\t',
+        _ => return None,
+    })
+}
+
+fn push_simple_escape<'de, R: Read<'de>>(
+    read: &mut R,
+    scratch: &mut Vec<u8>,
+    escape: u8,
+    syntax: Syntax,
+) -> Result<()> {
+    match simple_escape(escape, syntax) {
+        Some(ch) => {
+            scratch.push(ch);
+            Ok(())
+        }
+        None => Err(Error::UnexpectedToken(finish_char(read, escape)?)),
+    }
+}
+
+/// Decodes the escape sequence after a backslash into `scratch`, joining
+/// UTF-16 surrogate pairs into one character.
+fn parse_escape<'de, R: Read<'de>>(
+    read: &mut R,
+    scratch: &mut Vec<u8>,
+    lone_surrogates: LoneSurrogates,
+    syntax: Syntax,
+) -> Result<()> {
+    match read.next()?.ok_or(Error::UnexpectedEnd)? {
+        b'u' => {}
+        escape => return push_simple_escape(read, scratch, escape, syntax),
+    }
+    let mut code = parse_hex4(read)?;
+    loop {
+        if !(0xD800..0xDC00).contains(&code) {
+            return match char::from_u32(code) {
+                Some(ch) => {
+                    push_char(scratch, ch);
+                    Ok(())
+                }
+                None => push_lone(scratch, code, lone_surrogates),
+            };
+        }
+        if read.peek()? != Some(b'\\') {
+            return push_lone(scratch, code, lone_surrogates);
+        }
+        read.discard();
+        match read.next()?.ok_or(Error::UnexpectedEnd)? {
+            b'u' => {}
+            escape => {
+                push_lone(scratch, code, lone_surrogates)?;
+                return push_simple_escape(read, scratch, escape, syntax);
+            }
+        }
+        let low = parse_hex4(read)?;
+        if (0xDC00..0xE000).contains(&low) {
+            let ch = char::from_u32(0x10000 + ((code - 0xD800) << 10) + (low - 0xDC00));
+            push_char(scratch, ch.unwrap_or(char::REPLACEMENT_CHARACTER));
+            return Ok(());
+        }
+        push_lone(scratch, code, lone_surrogates)?;
+        code = low;
+    }
+}
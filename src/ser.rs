@@ -2,20 +2,80 @@ use alloc::format;
 use alloc::string::String;
 use alloc::string::ToString;
 use alloc::vec::Vec;
-use serde::serde_if_integer128;
-use serde::{ser, Serialize};
+use core::fmt;
+use serde::ser::{self, Impossible, Serialize};
 
-use crate::{Error, Result};
+use crate::config::{Bools, Integers, NonFinite, MAX_DEPTH};
+use crate::{Config, Error, Result};
 
-pub struct Serializer(Vec<u8>);
+/// An output sink for `Serializer`.
+pub trait Write {
+    fn write_str(&mut self, s: &str) -> Result<()>;
+
+    fn flush(&mut self) -> Result<()> {
+        Ok(())
+    }
+}
+
+impl Write for Vec<u8> {
+    fn write_str(&mut self, s: &str) -> Result<()> {
+        self.extend_from_slice(s.as_bytes());
+        Ok(())
+    }
+}
+
+impl<W: Write + ?Sized> Write for &mut W {
+    fn write_str(&mut self, s: &str) -> Result<()> {
+        (**self).write_str(s)
+    }
+
+    fn flush(&mut self) -> Result<()> {
+        (**self).flush()
+    }
+}
+
+/// Adapts a `std::io::Write` into a `Write`.
+#[cfg(feature = "std")]
+pub struct IoWrite<W>(pub W);
+
+#[cfg(feature = "std")]
+impl<W: std::io::Write> Write for IoWrite<W> {
+    fn write_str(&mut self, s: &str) -> Result<()> {
+        self.0.write_all(s.as_bytes()).map_err(Error::Io)
+    }
+
+    fn flush(&mut self) -> Result<()> {
+        self.0.flush().map_err(Error::Io)
+    }
+}
+
+/// Adapts a `core::fmt::Write` into a `Write`.
+pub struct FmtWrite<W>(pub W);
+
+impl<W: fmt::Write> Write for FmtWrite<W> {
+    fn write_str(&mut self, s: &str) -> Result<()> {
+        self.0.write_str(s).map_err(|_| Error::Fmt)
+    }
+}
+
+/// 2^53 - 1, the largest integer a JavaScript number holds exactly.
+const MAX_SAFE_INTEGER: u64 = (1 << 53) - 1;
+
+pub struct Serializer<W = Vec<u8>> {
+    out: W,
+    indent: Option<String>,
+    depth: usize,
+    max_depth: usize,
+    config: Config,
+}
 
 pub fn to_string_with_capacity<T>(value: &T, capacity: usize) -> Result<String>
 where
     T: Serialize,
 {
-    let mut serializer = Serializer(Vec::with_capacity(capacity));
+    let mut serializer = Serializer::new(Vec::with_capacity(capacity));
     value.serialize(&mut serializer)?;
-    Ok(unsafe { String::from_utf8_unchecked(serializer.0) })
+    Ok(unsafe { String::from_utf8_unchecked(serializer.out) })
 }
 
 pub fn to_string<T>(value: &T) -> Result<String>
@@ -25,126 +85,355 @@ where
     to_string_with_capacity(value, 128)
 }
 
-impl Serializer {
-    fn append(&mut self, data: &'static str) {
-        self.0.extend_from_slice(data.as_bytes());
-    }
-    fn append_string(&mut self, data: String) {
-        self.0.extend_from_slice(data.as_bytes());
-    }
-    fn serialize_simple_string(&mut self, num: String) {
-        self.append("\"");
-        self.append_string(num);
-        self.append("\"");
-    }
-    fn json_escape(&mut self, src: &str) {
-        const ESCAPE: [&str; 6] = ["\\b", "\\t", "\\n", "\\u000b", "\\f", "\\r"];
-        for ch in src.chars() {
-            let c = ch as u8 - 8;
-            if c < 6 {
-                self.append(ESCAPE[c as usize]);
-                continue;
-            }
-            if ch <= '\x1f' {
-                self.append_string(format!("\\u00{:0>2x}", ch as u8));
-                continue;
+/// Like `to_string`, following the wire conventions of `config`.
+pub fn to_string_with_config<T>(value: &T, config: Config) -> Result<String>
+where
+    T: ?Sized + Serialize,
+{
+    let mut serializer = Serializer::new(Vec::with_capacity(128)).with_config(config);
+    value.serialize(&mut serializer)?;
+    Ok(unsafe { String::from_utf8_unchecked(serializer.out) })
+}
+
+/// Like `to_string`, but puts every array element and object member on its own
+/// line, indented by two spaces per level.
+pub fn to_string_pretty<T>(value: &T) -> Result<String>
+where
+    T: Serialize,
+{
+    to_string_pretty_with_indent(value, "  ")
+}
+
+/// Like `to_string_pretty`, with `indent` repeated once per nesting level.
+pub fn to_string_pretty_with_indent<T>(value: &T, indent: &str) -> Result<String>
+where
+    T: Serialize,
+{
+    let mut serializer = Serializer::pretty(Vec::with_capacity(128), indent);
+    value.serialize(&mut serializer)?;
+    Ok(unsafe { String::from_utf8_unchecked(serializer.out) })
+}
+
+/// Serializes `value` into an `io::Write`, such as a file or socket.
+/// Wrap unbuffered writers in a `BufWriter`, as every token is written separately.
+#[cfg(feature = "std")]
+pub fn to_writer<W, T>(writer: W, value: &T) -> Result<()>
+where
+    W: std::io::Write,
+    T: ?Sized + Serialize,
+{
+    value.serialize(&mut Serializer::new(IoWrite(writer)))
+}
+
+#[cfg(feature = "std")]
+pub fn to_writer_pretty<W, T>(writer: W, value: &T) -> Result<()>
+where
+    W: std::io::Write,
+    T: ?Sized + Serialize,
+{
+    value.serialize(&mut Serializer::pretty(IoWrite(writer), "  "))
+}
+
+/// Serializes `value` into a `fmt::Write`, such as a `String` or a `Formatter`.
+pub fn to_fmt<W, T>(writer: W, value: &T) -> Result<()>
+where
+    W: fmt::Write,
+    T: ?Sized + Serialize,
+{
+    value.serialize(&mut Serializer::new(FmtWrite(writer)))
+}
+
+/// Formats the wrapped value as json4web, e.g. `format!("{}", Display(&value))`.
+/// Serialization errors surface as `fmt::Error`.
+#[derive(Clone, Copy)]
+pub struct Display<'a, T: ?Sized>(pub &'a T);
+
+impl<'a, T: ?Sized + Serialize> fmt::Display for Display<'a, T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        to_fmt(f, self.0).map_err(|_| fmt::Error)
+    }
+}
+
+/// Writes one value per line, as in NDJSON / JSON Lines.
+///
+/// Each record is serialized into a buffer that is reused across records and
+/// written out as a whole, so a failed record leaves no partial line behind.
+/// Strings escape all control characters, so records never contain a raw newline.
+pub struct JsonLinesWriter<W> {
+    out: W,
+    buf: Vec<u8>,
+    flush_each_line: bool,
+    config: Config,
+}
+
+impl<W: Write> JsonLinesWriter<W> {
+    pub fn new(out: W) -> Self {
+        JsonLinesWriter {
+            out,
+            buf: Vec::with_capacity(128),
+            flush_each_line: false,
+            config: Config::json4web(),
+        }
+    }
+
+    pub fn with_config(mut self, config: Config) -> Self {
+        self.config = config;
+        self
+    }
+
+    /// Flushes the output after every record. Off by default.
+    pub fn flush_each_line(mut self, flush: bool) -> Self {
+        self.flush_each_line = flush;
+        self
+    }
+
+    /// Serializes `value` followed by `\n`.
+    pub fn write<T>(&mut self, value: &T) -> Result<()>
+    where
+        T: ?Sized + Serialize,
+    {
+        self.buf.clear();
+        value.serialize(&mut Serializer::new(&mut self.buf).with_config(self.config))?;
+        self.buf.push(b'\n');
+        self.out
+            .write_str(unsafe { core::str::from_utf8_unchecked(&self.buf) })?;
+        if self.flush_each_line {
+            self.out.flush()?;
+        }
+        Ok(())
+    }
+
+    pub fn flush(&mut self) -> Result<()> {
+        self.out.flush()
+    }
+
+    pub fn into_inner(self) -> W {
+        self.out
+    }
+}
+
+impl<W: Write> Serializer<W> {
+    pub fn new(out: W) -> Self {
+        Serializer {
+            out,
+            indent: None,
+            depth: 0,
+            max_depth: MAX_DEPTH,
+            config: Config::json4web(),
+        }
+    }
+
+    /// A serializer that writes `indent` once per nesting level, see `to_string_pretty`.
+    pub fn pretty(out: W, indent: &str) -> Self {
+        Serializer {
+            out,
+            indent: Some(String::from(indent)),
+            depth: 0,
+            max_depth: MAX_DEPTH,
+            config: Config::json4web(),
+        }
+    }
+
+    /// Switches to the wire conventions of `config`, e.g. `Config::standard()`.
+    pub fn with_config(mut self, config: Config) -> Self {
+        self.config = config;
+        self
+    }
+
+    /// Sets how many arrays and objects may be open at once before
+    /// `Error::RecursionLimitExceeded`. It is 128 by default.
+    pub fn max_depth(mut self, depth: usize) -> Self {
+        self.max_depth = depth;
+        self
+    }
+
+    pub fn into_inner(self) -> W {
+        self.out
+    }
+
+    fn append(&mut self, data: &str) -> Result<()> {
+        self.out.write_str(data)
+    }
+    fn append_string(&mut self, data: String) -> Result<()> {
+        self.out.write_str(&data)
+    }
+    fn newline(&mut self) -> Result<()> {
+        if let Some(indent) = &self.indent {
+            self.out.write_str("\n")?;
+            for _ in 0..self.depth {
+                self.out.write_str(indent)?;
             }
-            match ch {
-                '"' => self.append("\\\""),
-                '\\' => self.append("\\\\"),
-                '/' => self.append("\\/"),
-                other => self.append_string(other.to_string()),
+        }
+        Ok(())
+    }
+    fn begin_compound(&mut self, open: &'static str) -> Result<()> {
+        if self.depth >= self.max_depth {
+            return Err(Error::RecursionLimitExceeded);
+        }
+        self.append(open)?;
+        self.depth += 1;
+        Ok(())
+    }
+    fn begin_item(&mut self, first: bool) -> Result<()> {
+        if !first {
+            self.append(",")?;
+        }
+        self.newline()
+    }
+    fn end_compound(&mut self, empty: bool, close: &'static str) -> Result<()> {
+        self.depth -= 1;
+        if !empty {
+            self.newline()?;
+        }
+        self.append(close)
+    }
+    fn key_separator(&mut self) -> Result<()> {
+        self.append(if self.indent.is_some() { ": " } else { ":" })
+    }
+    /// Writes a 64- or 128-bit integer, quoted unless the config says otherwise.
+    /// `safe` tells whether it is exactly representable as a JavaScript number.
+    fn serialize_wide_integer(&mut self, num: String, safe: bool) -> Result<()> {
+        match self.config.integers {
+            Integers::Quoted => self.serialize_simple_string(num),
+            Integers::Bare => self.append_string(num),
+            Integers::QuoteUnsafe if safe => self.append_string(num),
+            Integers::QuoteUnsafe => self.serialize_simple_string(num),
+        }
+    }
+    fn bool_token(&self, v: bool) -> &'static str {
+        match (self.config.bools, v) {
+            (Bools::Numeric, true) => "1",
+            (Bools::Numeric, false) => "0",
+            (Bools::Literal, true) => "true",
+            (Bools::Literal, false) => "false",
+        }
+    }
+    fn serialize_non_finite(&mut self, v: f64) -> Result<()> {
+        match self.config.non_finite {
+            NonFinite::Error => Err(Error::NonFiniteFloat),
+            NonFinite::Null => self.append("null"),
+            NonFinite::Strings if v.is_nan() => self.append("\"NaN\""),
+            NonFinite::Strings if v > 0.0 => self.append("\"Infinity\""),
+            NonFinite::Strings => self.append("\"-Infinity\""),
+        }
+    }
+    fn serialize_simple_string(&mut self, num: String) -> Result<()> {
+        self.append("\"")?;
+        self.append_string(num)?;
+        self.append("\"")
+    }
+    fn json_escape(&mut self, src: &str) -> Result<()> {
+        let mut start = 0;
+        for (at, ch) in src.char_indices() {
+            let escape = match ch {
+                '"' => "\\\"",
+                '\\' => "\\\\",
+                '/' => "\\/",
+                '\x08' => "\\b",
+                '\t' => "\\t",
+                '\n' => "\\n",
+                '\x0c' => "\\f",
+                '\r' => "\\r",
+                '\x00'..='\x1f' => "",
+                _ => continue,
+            };
+            self.append(&src[start..at])?;
+            if escape.is_empty() {
+                self.append_string(format!("\\u00{:0>2x}", ch as u8))?;
+            } else {
+                self.append(escape)?;
             }
+            start = at + 1;
         }
+        self.append(&src[start..])
     }
 }
-impl<'a> ser::Serializer for &'a mut Serializer {
+impl<'a, W: Write> ser::Serializer for &'a mut Serializer<W> {
     type Ok = ();
 
     type Error = Error;
 
-    type SerializeSeq = Compound<'a>;
-    type SerializeTuple = Compound<'a>;
-    type SerializeTupleStruct = Compound<'a>;
-    type SerializeTupleVariant = Compound<'a>;
-    type SerializeMap = Compound<'a>;
-    type SerializeStruct = Compound<'a>;
-    type SerializeStructVariant = Compound<'a>;
+    type SerializeSeq = Compound<'a, W>;
+    type SerializeTuple = Compound<'a, W>;
+    type SerializeTupleStruct = Compound<'a, W>;
+    type SerializeTupleVariant = Compound<'a, W>;
+    type SerializeMap = Compound<'a, W>;
+    type SerializeStruct = Compound<'a, W>;
+    type SerializeStructVariant = Compound<'a, W>;
 
     fn serialize_bool(self, v: bool) -> Result<()> {
-        self.append(if v { "1" } else { "0" });
+        self.append(self.bool_token(v))?;
         Ok(())
     }
 
     fn serialize_i8(self, v: i8) -> Result<()> {
-        self.append_string(v.to_string());
+        self.append_string(v.to_string())?;
         Ok(())
     }
 
     fn serialize_i16(self, v: i16) -> Result<()> {
-        self.append_string(v.to_string());
+        self.append_string(v.to_string())?;
         Ok(())
     }
 
     fn serialize_i32(self, v: i32) -> Result<()> {
-        self.append_string(v.to_string());
+        self.append_string(v.to_string())?;
         Ok(())
     }
 
     fn serialize_i64(self, v: i64) -> Result<()> {
-        self.serialize_simple_string(v.to_string());
+        self.serialize_wide_integer(v.to_string(), v.unsigned_abs() <= MAX_SAFE_INTEGER)?;
         Ok(())
     }
 
     fn serialize_u8(self, v: u8) -> Result<()> {
-        self.append_string(v.to_string());
+        self.append_string(v.to_string())?;
         Ok(())
     }
 
     fn serialize_u16(self, v: u16) -> Result<()> {
-        self.append_string(v.to_string());
+        self.append_string(v.to_string())?;
         Ok(())
     }
 
     fn serialize_u32(self, v: u32) -> Result<()> {
-        self.append_string(v.to_string());
+        self.append_string(v.to_string())?;
         Ok(())
     }
 
     fn serialize_u64(self, v: u64) -> Result<()> {
-        self.serialize_simple_string(v.to_string());
+        self.serialize_wide_integer(v.to_string(), v <= MAX_SAFE_INTEGER)?;
         Ok(())
     }
 
-    serde_if_integer128! {
-
-        fn serialize_u128(self, v: u128) -> Result<()> {
-            self.serialize_simple_string(v.to_string());
-            Ok(())
-        }
-
-        fn serialize_i128(self, v: i128) -> Result<()> {
-            self.serialize_simple_string(v.to_string());
-            Ok(())
-        }
+    fn serialize_u128(self, v: u128) -> Result<()> {
+        self.serialize_wide_integer(v.to_string(), v <= u128::from(MAX_SAFE_INTEGER))?;
+        Ok(())
+    }
 
+    fn serialize_i128(self, v: i128) -> Result<()> {
+        self.serialize_wide_integer(
+            v.to_string(),
+            v.unsigned_abs() <= u128::from(MAX_SAFE_INTEGER),
+        )?;
+        Ok(())
     }
 
     fn serialize_f32(self, v: f32) -> Result<()> {
         if !v.is_finite() {
-            return self.serialize_unit();
+            return self.serialize_non_finite(v.into());
         }
         let mut buffer = ryu::Buffer::new();
-        self.append_string(buffer.format_finite(v).to_string());
+        self.append_string(buffer.format_finite(v).to_string())?;
         Ok(())
     }
 
     fn serialize_f64(self, v: f64) -> Result<()> {
         if !v.is_finite() {
-            return self.serialize_unit();
+            return self.serialize_non_finite(v);
         }
         let mut buffer = ryu::Buffer::new();
-        self.append_string(buffer.format_finite(v).to_string());
+        self.append_string(buffer.format_finite(v).to_string())?;
         Ok(())
     }
 
@@ -153,14 +442,15 @@ impl<'a> ser::Serializer for &'a mut Serializer {
     }
 
     fn serialize_str(self, v: &str) -> Result<()> {
-        self.append("\"");
-        self.json_escape(v);
-        self.append("\"");
+        self.append("\"")?;
+        self.json_escape(v)?;
+        self.append("\"")?;
         Ok(())
     }
 
     fn serialize_bytes(self, v: &[u8]) -> Result<()> {
-        self.serialize_simple_string(base64::encode_config(v, base64::URL_SAFE));
+        let encoded = self.config.encode_bytes(v);
+        self.serialize_simple_string(encoded)?;
         Ok(())
     }
 
@@ -176,7 +466,7 @@ impl<'a> ser::Serializer for &'a mut Serializer {
     }
 
     fn serialize_unit(self) -> Result<()> {
-        self.append("null");
+        self.append("null")?;
         Ok(())
     }
 
@@ -210,16 +500,17 @@ impl<'a> ser::Serializer for &'a mut Serializer {
     where
         T: ?Sized + Serialize,
     {
-        self.append("{");
+        self.begin_compound("{")?;
+        self.begin_item(true)?;
         variant.serialize(&mut *self)?;
-        self.append(":");
+        self.key_separator()?;
         value.serialize(&mut *self)?;
-        self.append("}");
+        self.end_compound(false, "}")?;
         Ok(())
     }
 
     fn serialize_seq(self, _len: Option<usize>) -> Result<Self::SerializeSeq> {
-        self.append("[");
+        self.begin_compound("[")?;
         Ok(Compound(self, true))
     }
 
@@ -242,14 +533,16 @@ impl<'a> ser::Serializer for &'a mut Serializer {
         variant: &'static str,
         _len: usize,
     ) -> Result<Self::SerializeTupleVariant> {
-        self.append("{");
+        self.begin_compound("{")?;
+        self.begin_item(true)?;
         variant.serialize(&mut *self)?;
-        self.append(":[");
+        self.key_separator()?;
+        self.begin_compound("[")?;
         Ok(Compound(self, true))
     }
 
     fn serialize_map(self, _len: Option<usize>) -> Result<Self::SerializeMap> {
-        self.append("{");
+        self.begin_compound("{")?;
         Ok(Compound(self, true))
     }
 
@@ -264,26 +557,32 @@ impl<'a> ser::Serializer for &'a mut Serializer {
         variant: &'static str,
         _len: usize,
     ) -> Result<Self::SerializeStructVariant> {
-        self.append("{");
+        self.begin_compound("{")?;
+        self.begin_item(true)?;
         variant.serialize(&mut *self)?;
-        self.append(":{");
+        self.key_separator()?;
+        self.begin_compound("{")?;
         Ok(Compound(self, true))
     }
 }
 
-pub struct Compound<'a>(&'a mut Serializer, bool);
-impl<'a> Compound<'a> {
-    fn append(&mut self, data: &'static str) {
-        self.0.append(data)
-    }
+pub struct Compound<'a, W>(&'a mut Serializer<W>, bool);
+impl<'a, W: Write> Compound<'a, W> {
     fn first(&mut self) -> bool {
         let b = self.1;
         self.1 = false;
         b
     }
+    fn begin_item(&mut self) -> Result<()> {
+        let first = self.first();
+        self.0.begin_item(first)
+    }
+    fn close(&mut self, close: &'static str) -> Result<()> {
+        self.0.end_compound(self.1, close)
+    }
 }
 
-impl<'a> ser::SerializeSeq for Compound<'a> {
+impl<'a, W: Write> ser::SerializeSeq for Compound<'a, W> {
     type Ok = ();
     type Error = Error;
 
@@ -291,19 +590,17 @@ impl<'a> ser::SerializeSeq for Compound<'a> {
     where
         T: ?Sized + Serialize,
     {
-        if !self.first() {
-            self.append(",");
-        }
+        self.begin_item()?;
         value.serialize(&mut *self.0)
     }
 
     fn end(mut self) -> Result<()> {
-        self.append("]");
+        self.close("]")?;
         Ok(())
     }
 }
 
-impl<'a> ser::SerializeTuple for Compound<'a> {
+impl<'a, W: Write> ser::SerializeTuple for Compound<'a, W> {
     type Ok = ();
     type Error = Error;
 
@@ -311,19 +608,17 @@ impl<'a> ser::SerializeTuple for Compound<'a> {
     where
         T: ?Sized + Serialize,
     {
-        if !self.first() {
-            self.append(",");
-        }
+        self.begin_item()?;
         value.serialize(&mut *self.0)
     }
 
     fn end(mut self) -> Result<()> {
-        self.append("]");
+        self.close("]")?;
         Ok(())
     }
 }
 
-impl<'a> ser::SerializeTupleStruct for Compound<'a> {
+impl<'a, W: Write> ser::SerializeTupleStruct for Compound<'a, W> {
     type Ok = ();
     type Error = Error;
 
@@ -331,19 +626,17 @@ impl<'a> ser::SerializeTupleStruct for Compound<'a> {
     where
         T: ?Sized + Serialize,
     {
-        if !self.first() {
-            self.append(",");
-        }
+        self.begin_item()?;
         value.serialize(&mut *self.0)
     }
 
     fn end(mut self) -> Result<()> {
-        self.append("]");
+        self.close("]")?;
         Ok(())
     }
 }
 
-impl<'a> ser::SerializeTupleVariant for Compound<'a> {
+impl<'a, W: Write> ser::SerializeTupleVariant for Compound<'a, W> {
     type Ok = ();
     type Error = Error;
 
@@ -351,19 +644,18 @@ impl<'a> ser::SerializeTupleVariant for Compound<'a> {
     where
         T: ?Sized + Serialize,
     {
-        if !self.first() {
-            self.append(",");
-        }
+        self.begin_item()?;
         value.serialize(&mut *self.0)
     }
 
     fn end(mut self) -> Result<()> {
-        self.append("]}");
+        self.close("]")?;
+        self.0.end_compound(false, "}")?;
         Ok(())
     }
 }
 
-impl<'a> ser::SerializeMap for Compound<'a> {
+impl<'a, W: Write> ser::SerializeMap for Compound<'a, W> {
     type Ok = ();
     type Error = Error;
 
@@ -371,27 +663,25 @@ impl<'a> ser::SerializeMap for Compound<'a> {
     where
         T: ?Sized + Serialize,
     {
-        if !self.first() {
-            self.append(",");
-        }
-        key.serialize(&mut *self.0)
+        self.begin_item()?;
+        key.serialize(MapKeySerializer(&mut *self.0))
     }
 
     fn serialize_value<T>(&mut self, value: &T) -> Result<()>
     where
         T: ?Sized + Serialize,
     {
-        self.append(":");
+        self.0.key_separator()?;
         value.serialize(&mut *self.0)
     }
 
     fn end(mut self) -> Result<()> {
-        self.append("}");
+        self.close("}")?;
         Ok(())
     }
 }
 
-impl<'a> ser::SerializeStruct for Compound<'a> {
+impl<'a, W: Write> ser::SerializeStruct for Compound<'a, W> {
     type Ok = ();
     type Error = Error;
 
@@ -399,21 +689,19 @@ impl<'a> ser::SerializeStruct for Compound<'a> {
     where
         T: ?Sized + Serialize,
     {
-        if !self.first() {
-            self.append(",");
-        }
+        self.begin_item()?;
         key.serialize(&mut *self.0)?;
-        self.append(":");
+        self.0.key_separator()?;
         value.serialize(&mut *self.0)
     }
 
     fn end(mut self) -> Result<()> {
-        self.append("}");
+        self.close("}")?;
         Ok(())
     }
 }
 
-impl<'a> ser::SerializeStructVariant for Compound<'a> {
+impl<'a, W: Write> ser::SerializeStructVariant for Compound<'a, W> {
     type Ok = ();
     type Error = Error;
 
@@ -421,16 +709,193 @@ impl<'a> ser::SerializeStructVariant for Compound<'a> {
     where
         T: ?Sized + Serialize,
     {
-        if !self.first() {
-            self.append(",");
-        }
+        self.begin_item()?;
         key.serialize(&mut *self.0)?;
-        self.append(":");
+        self.0.key_separator()?;
         value.serialize(&mut *self.0)
     }
 
     fn end(mut self) -> Result<()> {
-        self.append("}}");
+        self.close("}")?;
+        self.0.end_compound(false, "}")?;
         Ok(())
     }
 }
+
+/// Writes map keys as JSON strings: integers, bools, chars and unit variants
+/// are stringified, bytes are encoded like values, anything else is rejected.
+struct MapKeySerializer<'a, W>(&'a mut Serializer<W>);
+
+fn key_must_be_a_string() -> Error {
+    <Error as ser::Error>::custom("map key must be a string, integer, bool, char or unit variant")
+}
+
+impl<'a, W: Write> ser::Serializer for MapKeySerializer<'a, W> {
+    type Ok = ();
+    type Error = Error;
+
+    type SerializeSeq = Impossible<(), Error>;
+    type SerializeTuple = Impossible<(), Error>;
+    type SerializeTupleStruct = Impossible<(), Error>;
+    type SerializeTupleVariant = Impossible<(), Error>;
+    type SerializeMap = Impossible<(), Error>;
+    type SerializeStruct = Impossible<(), Error>;
+    type SerializeStructVariant = Impossible<(), Error>;
+
+    fn serialize_bool(self, v: bool) -> Result<()> {
+        let v = self.0.bool_token(v);
+        self.0.serialize_simple_string(v.to_string())
+    }
+
+    fn serialize_i8(self, v: i8) -> Result<()> {
+        self.0.serialize_simple_string(v.to_string())
+    }
+
+    fn serialize_i16(self, v: i16) -> Result<()> {
+        self.0.serialize_simple_string(v.to_string())
+    }
+
+    fn serialize_i32(self, v: i32) -> Result<()> {
+        self.0.serialize_simple_string(v.to_string())
+    }
+
+    fn serialize_i64(self, v: i64) -> Result<()> {
+        self.0.serialize_simple_string(v.to_string())
+    }
+
+    fn serialize_u8(self, v: u8) -> Result<()> {
+        self.0.serialize_simple_string(v.to_string())
+    }
+
+    fn serialize_u16(self, v: u16) -> Result<()> {
+        self.0.serialize_simple_string(v.to_string())
+    }
+
+    fn serialize_u32(self, v: u32) -> Result<()> {
+        self.0.serialize_simple_string(v.to_string())
+    }
+
+    fn serialize_u64(self, v: u64) -> Result<()> {
+        self.0.serialize_simple_string(v.to_string())
+    }
+
+    fn serialize_u128(self, v: u128) -> Result<()> {
+        self.0.serialize_simple_string(v.to_string())
+    }
+
+    fn serialize_i128(self, v: i128) -> Result<()> {
+        self.0.serialize_simple_string(v.to_string())
+    }
+
+    fn serialize_f32(self, _v: f32) -> Result<()> {
+        Err(key_must_be_a_string())
+    }
+
+    fn serialize_f64(self, _v: f64) -> Result<()> {
+        Err(key_must_be_a_string())
+    }
+
+    fn serialize_char(self, v: char) -> Result<()> {
+        ser::Serializer::serialize_char(self.0, v)
+    }
+
+    fn serialize_str(self, v: &str) -> Result<()> {
+        ser::Serializer::serialize_str(self.0, v)
+    }
+
+    fn serialize_bytes(self, v: &[u8]) -> Result<()> {
+        ser::Serializer::serialize_bytes(self.0, v)
+    }
+
+    fn serialize_none(self) -> Result<()> {
+        Err(key_must_be_a_string())
+    }
+
+    fn serialize_some<T>(self, _value: &T) -> Result<()>
+    where
+        T: ?Sized + Serialize,
+    {
+        Err(key_must_be_a_string())
+    }
+
+    fn serialize_unit(self) -> Result<()> {
+        Err(key_must_be_a_string())
+    }
+
+    fn serialize_unit_struct(self, _name: &'static str) -> Result<()> {
+        Err(key_must_be_a_string())
+    }
+
+    fn serialize_unit_variant(
+        self,
+        _name: &'static str,
+        _variant_index: u32,
+        variant: &'static str,
+    ) -> Result<()> {
+        ser::Serializer::serialize_str(self.0, variant)
+    }
+
+    fn serialize_newtype_struct<T>(self, _name: &'static str, value: &T) -> Result<()>
+    where
+        T: ?Sized + Serialize,
+    {
+        value.serialize(self)
+    }
+
+    fn serialize_newtype_variant<T>(
+        self,
+        _name: &'static str,
+        _variant_index: u32,
+        _variant: &'static str,
+        _value: &T,
+    ) -> Result<()>
+    where
+        T: ?Sized + Serialize,
+    {
+        Err(key_must_be_a_string())
+    }
+
+    fn serialize_seq(self, _len: Option<usize>) -> Result<Self::SerializeSeq> {
+        Err(key_must_be_a_string())
+    }
+
+    fn serialize_tuple(self, _len: usize) -> Result<Self::SerializeTuple> {
+        Err(key_must_be_a_string())
+    }
+
+    fn serialize_tuple_struct(
+        self,
+        _name: &'static str,
+        _len: usize,
+    ) -> Result<Self::SerializeTupleStruct> {
+        Err(key_must_be_a_string())
+    }
+
+    fn serialize_tuple_variant(
+        self,
+        _name: &'static str,
+        _variant_index: u32,
+        _variant: &'static str,
+        _len: usize,
+    ) -> Result<Self::SerializeTupleVariant> {
+        Err(key_must_be_a_string())
+    }
+
+    fn serialize_map(self, _len: Option<usize>) -> Result<Self::SerializeMap> {
+        Err(key_must_be_a_string())
+    }
+
+    fn serialize_struct(self, _name: &'static str, _len: usize) -> Result<Self::SerializeStruct> {
+        Err(key_must_be_a_string())
+    }
+
+    fn serialize_struct_variant(
+        self,
+        _name: &'static str,
+        _variant_index: u32,
+        _variant: &'static str,
+        _len: usize,
+    ) -> Result<Self::SerializeStructVariant> {
+        Err(key_must_be_a_string())
+    }
+}
@@ -0,0 +1,130 @@
+//! Async adapters that turn byte streams into streams of values, on top of
+//! `push::PushParser`. Enabled by the `futures` feature.
+//!
+//! Chunks are only pulled from the source when no complete value is buffered,
+//! so a slow consumer slows down the producer instead of filling memory.
+
+use alloc::vec;
+use alloc::vec::Vec;
+use core::marker::PhantomData;
+use core::pin::Pin;
+use core::task::{Context, Poll};
+use futures_core::{ready, Stream};
+use futures_io::AsyncRead;
+use serde::de::DeserializeOwned;
+
+use crate::push::PushParser;
+use crate::{Error, Result};
+
+/// Deserializes the values in a stream of byte chunks, such as `Bytes`.
+///
+/// Pass `PushParser::new()` for NDJSON or concatenated values, or
+/// `PushParser::array_elements()` for the elements of a top-level array.
+pub fn from_chunks<S, T>(chunks: S, parser: PushParser) -> Chunks<S, T>
+where
+    S: Stream + Unpin,
+    S::Item: AsRef<[u8]>,
+    T: DeserializeOwned,
+{
+    Chunks {
+        chunks,
+        parser,
+        done: false,
+        output: PhantomData,
+    }
+}
+
+/// Deserializes the values read from an `AsyncRead`, see `from_chunks`.
+pub fn from_async_read<R, T>(reader: R, parser: PushParser) -> AsyncReader<R, T>
+where
+    R: AsyncRead + Unpin,
+    T: DeserializeOwned,
+{
+    AsyncReader {
+        reader,
+        parser,
+        buf: vec![0; 8192],
+        done: false,
+        failed: false,
+        output: PhantomData,
+    }
+}
+
+pub struct Chunks<S, T> {
+    chunks: S,
+    parser: PushParser,
+    done: bool,
+    output: PhantomData<fn() -> T>,
+}
+
+impl<S, T> Stream for Chunks<S, T>
+where
+    S: Stream + Unpin,
+    S::Item: AsRef<[u8]>,
+    T: DeserializeOwned,
+{
+    type Item = Result<T>;
+
+    fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Result<T>>> {
+        let this = self.get_mut();
+        loop {
+            if let Some(value) = this.parser.next_value() {
+                return Poll::Ready(Some(value));
+            }
+            if this.done || this.parser.is_failed() {
+                return Poll::Ready(None);
+            }
+            match ready!(Pin::new(&mut this.chunks).poll_next(cx)) {
+                Some(chunk) => this.parser.feed(chunk.as_ref()),
+                None => {
+                    this.done = true;
+                    this.parser.finish();
+                }
+            }
+        }
+    }
+}
+
+pub struct AsyncReader<R, T> {
+    reader: R,
+    parser: PushParser,
+    buf: Vec<u8>,
+    done: bool,
+    failed: bool,
+    output: PhantomData<fn() -> T>,
+}
+
+impl<R, T> Stream for AsyncReader<R, T>
+where
+    R: AsyncRead + Unpin,
+    T: DeserializeOwned,
+{
+    type Item = Result<T>;
+
+    fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Result<T>>> {
+        let this = self.get_mut();
+        loop {
+            if this.failed {
+                return Poll::Ready(None);
+            }
+            if let Some(value) = this.parser.next_value() {
+                return Poll::Ready(Some(value));
+            }
+            if this.done || this.parser.is_failed() {
+                return Poll::Ready(None);
+            }
+            match ready!(Pin::new(&mut this.reader).poll_read(cx, &mut this.buf)) {
+                Ok(0) => {
+                    this.done = true;
+                    this.parser.finish();
+                }
+                Ok(len) => this.parser.feed(&this.buf[..len]),
+                Err(err) if err.kind() == std::io::ErrorKind::Interrupted => {}
+                Err(err) => {
+                    this.failed = true;
+                    return Poll::Ready(Some(Err(Error::Io(err))));
+                }
+            }
+        }
+    }
+}
@@ -7,10 +7,11 @@ extern crate wasm_bindgen_test;
 
 use alloc::borrow::ToOwned;
 use alloc::fmt::Debug;
-use alloc::string::String;
+use alloc::string::{String, ToString};
 use alloc::vec::Vec;
 use core::cmp::PartialEq;
 use json4web::de::*;
+#[allow(deprecated)]
 use serde::serde_if_integer128;
 use serde_derive::Deserialize;
 
@@ -125,6 +126,7 @@ fn test_string() {
 
 #[test]
 #[wasm_bindgen_test]
+#[allow(deprecated)]
 fn test_number() {
     test(123u8, r#"123"#);
     test(12345u16, r#"12345"#);
@@ -148,4 +150,1248 @@ fn test_number() {
 #[wasm_bindgen_test]
 fn test_null() {
     test((), r#"null"#);
+    test(None::<u32>, r#"null"#);
+    test(vec![None, Some(1)], r#"[null,1]"#);
+}
+
+#[test]
+#[wasm_bindgen_test]
+fn test_exponent() {
+    test(1e20f64, r#"1e20"#);
+    test(1.5e-7f64, r#"1.5E-7"#);
+    test(1.5e7f64, r#"1.5e+7"#);
+    test(-0.0f64, r#"-0.0"#);
+    test(-2.5e-3f32, r#"-2.5e-3"#);
+    test(vec![1e20f64, 2.0], r#"[1e20,2E0]"#);
+    assert!(from_str::<'_, f64>("1e").is_err());
+    assert!(from_str::<'_, f64>("1.e5").is_err());
+    assert!(from_str::<'_, f64>(".5").is_err());
+    assert!(from_str::<'_, f64>("-").is_err());
+    assert!(from_str::<'_, Vec<f64>>("[1e+]").is_err());
+}
+
+#[cfg(test)]
+fn roundtrip_f64(v: f64) {
+    let j = json4web::ser::to_string(&v).unwrap();
+    assert_eq!(
+        v.to_bits(),
+        from_str::<'_, f64>(&j).unwrap().to_bits(),
+        "{}",
+        j
+    );
+}
+
+#[cfg(test)]
+fn roundtrip_f32(v: f32) {
+    let j = json4web::ser::to_string(&v).unwrap();
+    assert_eq!(
+        v.to_bits(),
+        from_str::<'_, f32>(&j).unwrap().to_bits(),
+        "{}",
+        j
+    );
+}
+
+#[test]
+#[wasm_bindgen_test]
+fn test_float_roundtrip() {
+    let f64s = [
+        0.0,
+        -0.0,
+        f64::MAX,
+        f64::MIN,
+        f64::MIN_POSITIVE,
+        f64::EPSILON,
+        f64::from_bits(1),
+        f64::from_bits(0x000f_ffff_ffff_ffff),
+        -f64::from_bits(1),
+        1e20,
+        1.5e-7,
+        123456789012345680000.0,
+    ];
+    for v in f64s.iter() {
+        roundtrip_f64(*v);
+    }
+    let f32s = [
+        0.0,
+        -0.0,
+        f32::MAX,
+        f32::MIN,
+        f32::MIN_POSITIVE,
+        f32::EPSILON,
+        f32::from_bits(1),
+        f32::from_bits(0x007f_ffff),
+        -f32::from_bits(1),
+        1e20,
+        1.5e-7,
+    ];
+    for v in f32s.iter() {
+        roundtrip_f32(*v);
+    }
+    let mut bits = 0u64;
+    while let Some(next) = bits.checked_add(0x0000_7a3f_9c1d_5e27) {
+        let v = f64::from_bits(next);
+        if v.is_finite() {
+            roundtrip_f64(v);
+        }
+        bits = next;
+    }
+    let mut bits = 0u32;
+    while let Some(next) = bits.checked_add(0x0001_3e4b) {
+        let v = f32::from_bits(next);
+        if v.is_finite() {
+            roundtrip_f32(v);
+        }
+        bits = next;
+    }
+}
+
+#[test]
+#[wasm_bindgen_test]
+fn test_unknown_fields() {
+    #[derive(Deserialize, PartialEq, Debug)]
+    struct Test {
+        int: u32,
+    }
+
+    let expected = || Test { int: 1 };
+    test(expected(), r#"{"a":"x\"y\\","int":1}"#);
+    test(expected(), r#"{"int":1,"b":-1.5e3}"#);
+    test(
+        expected(),
+        r#"{"c":null,"d":true,"e":false,"int":1,"f":"1234"}"#,
+    );
+    test(
+        expected(),
+        r#" { "g" : [ 1 , [ ] , { } , { "h" : [ "]" , "}" ] } ] , "int" : 1 } "#,
+    );
+    test(expected(), r#"{"int":1,"i":{"j":{"k":[""]}}}"#);
+    assert!(from_str::<'_, Test>(r#"{"a":[1,}"int":1}"#).is_err());
+    assert!(from_str::<'_, Test>(r#"{"a":{"b"},"int":1}"#).is_err());
+    assert!(from_str::<'_, Test>(r#"{"a":"unterminated"#).is_err());
+    assert!(from_str::<'_, Test>(r#"{"a":nul,"int":1}"#).is_err());
+    assert!(from_str::<'_, Test>("{\"a\":\"\\\u{e9}\",\"int\":1}").is_err());
+    let mut de = Deserializer::new("{\"a\":\"\\\u{e9}\",\"int\":1}").syntax(Syntax::Permissive);
+    assert_eq!(
+        expected(),
+        serde::Deserialize::deserialize(&mut de).unwrap()
+    );
+}
+
+#[test]
+#[wasm_bindgen_test]
+fn test_trailing_characters() {
+    #[derive(Deserialize, PartialEq, Debug)]
+    struct Test {
+        a: u32,
+    }
+
+    test(Test { a: 1 }, "{\"a\":1} \n\t ");
+    match from_str::<'_, Test>(r#"{"a":1}garbage"#)
+        .unwrap_err()
+        .kind()
+    {
+        json4web::Error::TrailingCharacters => {}
+        other => panic!("{:?}", other),
+    }
+    assert!(from_str::<'_, Test>(r#"{"a":1}{"a":2}"#).is_err());
+    assert!(from_slice::<'_, u32>(b"1 2").is_err());
+
+    let mut de = Deserializer::new(r#"{"a":1} {"a":2} "#);
+    assert_eq!(
+        Test { a: 1 },
+        serde::Deserialize::deserialize(&mut de).unwrap()
+    );
+    assert!(de.end().is_err());
+    assert_eq!(
+        Test { a: 2 },
+        serde::Deserialize::deserialize(&mut de).unwrap()
+    );
+    de.end().unwrap();
+}
+
+#[test]
+#[wasm_bindgen_test]
+fn test_error_position() {
+    use json4web::Position;
+
+    #[derive(Deserialize, PartialEq, Debug)]
+    struct Test {
+        a: u32,
+        b: Vec<u32>,
+    }
+
+    let err = from_str::<'_, Test>("{\n  \"a\": 1,\n  \"b\": [1, x]\n}").unwrap_err();
+    assert_eq!(
+        Some(Position {
+            offset: 23,
+            line: 3,
+            column: 12
+        }),
+        err.position()
+    );
+    assert_eq!(Some(23), err.offset());
+    assert_eq!(Some(3), err.line());
+    assert_eq!(Some(12), err.column());
+    match err.kind() {
+        json4web::Error::UnexpectedToken('x') => {}
+        other => panic!("{:?}", other),
+    }
+    assert_eq!(
+        "Unexpected token x at line 3 column 12 (offset 23), path b[1]",
+        err.to_string()
+    );
+
+    let err = from_str::<'_, Test>(r#"{"a":1}"#).unwrap_err();
+    assert_eq!(Some(6), err.offset());
+    assert!(err
+        .to_string()
+        .starts_with("custom error : missing field `b`"));
+
+    let err = from_str::<'_, u32>("\"é\" 1").unwrap_err();
+    assert_eq!(Some(0), err.offset());
+    let err = from_str::<'_, String>("\"é\" 1").unwrap_err();
+    assert_eq!(
+        Some((5, 1, 5)),
+        err.position().map(|p| (p.offset, p.line, p.column))
+    );
+
+    let err = from_slice::<'_, String>(b"\n\"a\xff\"").unwrap_err();
+    assert_eq!(
+        Some((3, 2, 3)),
+        err.position().map(|p| (p.offset, p.line, p.column))
+    );
+
+    let err = from_str::<'_, String>("\"\\\u{e9}\"").unwrap_err();
+    assert_eq!(
+        Some((4, 1, 4)),
+        err.position().map(|p| (p.offset, p.line, p.column))
+    );
+    match err.kind() {
+        json4web::Error::UnexpectedToken('\u{e9}') => {}
+        other => panic!("{:?}", other),
+    }
+    let err = from_str::<'_, String>("\"\\u00\u{e9}0\"").unwrap_err();
+    assert_eq!(Some(5), err.offset());
+    for j in [
+        "[\"\\ud800\\u\u{e9}\"]",
+        "{\"\\\u{e9}\":1}",
+        "[\"\\\u{1f300}\"]",
+    ]
+    .iter()
+    {
+        assert!(from_str::<'_, json4web::Value>(j).is_err(), "{}", j);
+        assert!(from_str::<'_, serde::de::IgnoredAny>(j).is_err(), "{}", j);
+    }
+}
+
+#[test]
+#[wasm_bindgen_test]
+fn test_error_path() {
+    use json4web::{Path, Segment};
+
+    #[derive(Deserialize, Debug)]
+    struct Item {
+        #[allow(dead_code)]
+        price: u32,
+    }
+    #[derive(Deserialize, Debug)]
+    struct Order {
+        #[allow(dead_code)]
+        items: Vec<Item>,
+    }
+    #[derive(Deserialize, Debug)]
+    struct Orders {
+        #[allow(dead_code)]
+        orders: Vec<Order>,
+    }
+    #[derive(Deserialize, Debug)]
+    enum E {
+        #[allow(dead_code)]
+        Struct { orders: Vec<Order> },
+    }
+
+    let j = r#"{"orders":[{"items":[]},{"items":[{"price":1},{ "price" : "x"}]}]}"#;
+    let err = from_str::<'_, Orders>(j).unwrap_err();
+    let expected = Path(vec![
+        Segment::Key("orders".to_owned()),
+        Segment::Index(1),
+        Segment::Key("items".to_owned()),
+        Segment::Index(1),
+        Segment::Key("price".to_owned()),
+    ]);
+    assert_eq!(Some(&expected), err.path());
+    assert_eq!("orders[1].items[1].price", expected.to_string());
+    assert!(err.to_string().ends_with(", path orders[1].items[1].price"));
+
+    let j = r#"{"Struct":{"orders":[{"items":[{}]}]}}"#;
+    let err = from_str::<'_, E>(j).unwrap_err();
+    assert_eq!("Struct.orders[0].items[0]", err.path().unwrap().to_string());
+
+    let err = from_str::<'_, Vec<u32>>("[1,2,x]").unwrap_err();
+    assert_eq!("[2]", err.path().unwrap().to_string());
+}
+
+#[test]
+#[wasm_bindgen_test]
+fn test_surrogate_pairs() {
+    test("\u{1f600}".to_owned(), r#""\uD83D\uDE00""#);
+    test("a\u{1f600}b".to_owned(), r#""a\ud83d\ude00b""#);
+    test(
+        "\u{1f600}\u{1f600}".to_owned(),
+        r#""\uD83D\uDE00\uD83D\uDE00""#,
+    );
+    test("\u{10ffff}".to_owned(), r#""\uDBFF\uDFFF""#);
+    test("\u{ffff}".to_owned(), r#""\uFFFF""#);
+
+    for j in [
+        r#""\uD83D""#,
+        r#""\uDE00""#,
+        r#""\uD83Dx""#,
+        r#""\uD83DA""#,
+        r#""\uDE00\uD83D""#,
+    ]
+    .iter()
+    {
+        match from_str::<'_, String>(j).unwrap_err().kind() {
+            json4web::Error::LoneSurrogate(_) => {}
+            other => panic!("{}: {:?}", j, other),
+        }
+    }
+
+    let replace = |j| -> String {
+        let mut de = Deserializer::new(j).lone_surrogates(LoneSurrogates::Replace);
+        let s = serde::Deserialize::deserialize(&mut de).unwrap();
+        de.end().unwrap();
+        s
+    };
+    assert_eq!("\u{fffd}", replace(r#""\uD83D""#));
+    assert_eq!("\u{fffd}x", replace(r#""\uD83Dx""#));
+    assert_eq!("\u{fffd}A", replace(r#""\uD83DA""#));
+    assert_eq!("\u{fffd}\u{fffd}", replace(r#""\uDE00\uD83D""#));
+    assert_eq!("\u{1f600}", replace(r#""\uD83D\uDE00""#));
+
+    assert!(from_str::<'_, String>(r#""\u12""#).is_err());
+    assert!(from_str::<'_, String>(r#""\u12"#).is_err());
+    assert!(from_str::<'_, String>(r#""\u12g4""#).is_err());
+}
+
+#[test]
+#[cfg(feature = "std")]
+fn test_reader() {
+    extern crate std;
+
+    /// Hands out one byte per read to exercise refills mid-token.
+    struct Trickle<'a>(&'a [u8]);
+    impl std::io::Read for Trickle<'_> {
+        fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+            match self.0.split_first() {
+                Some((b, rest)) if !buf.is_empty() => {
+                    buf[0] = *b;
+                    self.0 = rest;
+                    Ok(1)
+                }
+                _ => Ok(0),
+            }
+        }
+    }
+
+    #[derive(Deserialize, PartialEq, Debug)]
+    struct Test {
+        name: String,
+        id: u64,
+        tags: Vec<String>,
+        parent: Option<u32>,
+    }
+
+    let j = "{ \"name\": \"\\u00e9t\\u00e9 \\uD83D\\uDE00\", \"id\": \"18446744073709551615\",
+        \"tags\": [\"caf\u{e9}\", \"x\"], \"extra\": [1, {\"a\": null}], \"parent\": null }";
+    let expected = Test {
+        name: "\u{e9}t\u{e9} \u{1f600}".to_owned(),
+        id: u64::MAX,
+        tags: vec!["caf\u{e9}".to_owned(), "x".to_owned()],
+        parent: None,
+    };
+    assert_eq!(expected, from_reader::<_, Test>(j.as_bytes()).unwrap());
+    assert_eq!(
+        expected,
+        from_reader::<_, Test>(Trickle(j.as_bytes())).unwrap()
+    );
+
+    let j = "{\"caf\u{e9}\": [1,\n \"x\"]}";
+    let err = from_reader::<_, std::collections::BTreeMap<String, Vec<u32>>>(Trickle(j.as_bytes()))
+        .unwrap_err();
+    assert_eq!(Some(2), err.line());
+    assert_eq!(Some(2), err.column());
+    assert_eq!(Some(15), err.offset());
+    assert_eq!("caf\u{e9}[1]", err.path().unwrap().to_string());
+
+    let err = from_reader::<_, u32>("1 2".as_bytes()).unwrap_err();
+    assert_eq!(Some(2), err.offset());
+    match err.kind() {
+        json4web::Error::TrailingCharacters => {}
+        other => panic!("{:?}", other),
+    }
+    match from_reader::<_, String>(&b"\"\xff\""[..])
+        .unwrap_err()
+        .kind()
+    {
+        json4web::Error::Utf8Error(_) => {}
+        other => panic!("{:?}", other),
+    }
+    for j in [
+        &b"\"\xff\""[..],
+        b"\"\xc3\"",
+        b"\"\xed\xa0\x80\"",
+        b"\"\\\xff\"",
+    ]
+    .iter()
+    {
+        assert!(from_slice::<'_, serde::de::IgnoredAny>(j).is_err());
+        assert!(from_reader::<_, serde::de::IgnoredAny>(*j).is_err());
+    }
+}
+
+#[test]
+#[wasm_bindgen_test]
+fn test_stream() {
+    #[derive(Deserialize, PartialEq, Debug)]
+    struct Line {
+        msg: String,
+        n: u32,
+    }
+
+    let j = "{\"msg\":\"a\",\"n\":1}\n{\"msg\":\"b\\nc\",\n \"n\":2}\n\n{\"msg\":\"d\",\"n\":3} ";
+    let lines: Vec<_> = Deserializer::new(j)
+        .into_stream::<Line>()
+        .map(|line| {
+            let (offset, line) = line.unwrap();
+            (offset, line.msg, line.n)
+        })
+        .collect();
+    assert_eq!(
+        lines,
+        vec![
+            (0, "a".to_owned(), 1),
+            (18, "b\nc".to_owned(), 2),
+            (42, "d".to_owned(), 3),
+        ]
+    );
+
+    let mut stream = Deserializer::new("1 [2]").into_stream::<json4web::Value>();
+    assert_eq!(0, stream.next().unwrap().unwrap().0);
+    assert_eq!(1, stream.byte_offset());
+    assert_eq!(2, stream.next().unwrap().unwrap().0);
+    assert!(stream.next().is_none());
+
+    let mut stream = Deserializer::new("1 2\nx 3").into_stream::<u32>();
+    assert_eq!(1, stream.next().unwrap().unwrap().1);
+    assert_eq!(2, stream.next().unwrap().unwrap().1);
+    let err = stream.next().unwrap().unwrap_err();
+    assert_eq!(Some(4), err.offset());
+    assert_eq!(Some(2), err.line());
+    assert!(stream.next().is_none());
+
+    assert!(Deserializer::new(" \n")
+        .into_stream::<u32>()
+        .next()
+        .is_none());
+    let mut stream = Deserializer::new("truefalse").into_stream::<bool>();
+    let err = stream.next().unwrap().unwrap_err();
+    assert_eq!(Some(4), err.offset());
+    match err.kind() {
+        json4web::Error::TrailingCharacters => {}
+        other => panic!("{:?}", other),
+    }
+    assert!(stream.next().is_none());
+    for j in ["1\"a\"", "1x", "null1"].iter() {
+        let mut stream = Deserializer::new(j).into_stream::<json4web::Value>();
+        assert!(stream.any(|value| value.is_err()), "{}", j);
+    }
+    let values: Vec<_> = Deserializer::new("[1][2]{}\"a\"\"b\"1[3]")
+        .into_stream::<json4web::Value>()
+        .map(|value| value.unwrap().1)
+        .collect();
+    assert_eq!(7, values.len());
+}
+
+#[test]
+#[wasm_bindgen_test]
+fn test_array_iter() {
+    #[derive(Deserialize, PartialEq, Debug)]
+    struct Record {
+        id: u64,
+    }
+
+    let mut de = Deserializer::new(r#" [ {"id":"1"}, {"id":"2"} ,{"id":"3"} ] "#);
+    let mut records = de.array_iter::<Record>().unwrap();
+    assert_eq!(Record { id: 1 }, records.next().unwrap().unwrap());
+    let rest: Vec<_> = records.map(|r| r.unwrap().id).collect();
+    assert_eq!(vec![2, 3], rest);
+    de.end().unwrap();
+
+    let mut de = Deserializer::new("[]");
+    assert!(de.array_iter::<u32>().unwrap().next().is_none());
+    de.end().unwrap();
+
+    let mut de = Deserializer::new("[1,2 3]");
+    let results: Vec<_> = de.array_iter::<u32>().unwrap().collect();
+    assert_eq!(3, results.len());
+    assert_eq!(Some(5), results[2].as_ref().unwrap_err().offset());
+
+    let mut de = Deserializer::new("[1,x]");
+    let err = de.array_iter::<u32>().unwrap().nth(1).unwrap().unwrap_err();
+    assert_eq!("[1]", err.path().unwrap().to_string());
+
+    let mut de = Deserializer::new("[1,2");
+    let mut items = de.array_iter::<u32>().unwrap();
+    assert_eq!(2, items.nth(1).unwrap().unwrap());
+    match items.next().unwrap().unwrap_err().kind() {
+        json4web::Error::UnexpectedEnd => {}
+        other => panic!("{:?}", other),
+    }
+    assert!(items.next().is_none());
+
+    let err = Deserializer::new(" {}").array_iter::<u32>().err().unwrap();
+    assert_eq!(Some(1), err.offset());
+}
+
+#[test]
+#[wasm_bindgen_test]
+fn test_standard() {
+    use json4web::Config;
+
+    #[derive(Deserialize, PartialEq, Debug)]
+    struct Test {
+        ok: bool,
+        small: u32,
+        id: u64,
+        delta: i64,
+        big: u128,
+    }
+
+    let j = r#"{"ok":true,"small":7,"id":18446744073709551615,"delta":-5,"big":1267650600228229401496703205376}"#;
+    let expected = Test {
+        ok: true,
+        small: 7,
+        id: u64::MAX,
+        delta: -5,
+        big: 1 << 100,
+    };
+    assert_eq!(
+        expected,
+        from_str_with_config(j, Config::standard()).unwrap()
+    );
+    assert_eq!(
+        expected,
+        from_slice_with_config(j.as_bytes(), Config::standard()).unwrap()
+    );
+    assert!(from_str::<'_, Test>(j).is_err());
+
+    assert!(from_str_with_config::<bool>("1", Config::standard()).is_err());
+    assert!(from_str_with_config::<i64>(r#""1""#, Config::standard()).is_err());
+    assert!(from_str_with_config::<bool>("false", Config::standard()).is_ok());
+
+    let mut de = Deserializer::new("[false, 3]").with_config(Config::standard());
+    let v: (bool, u64) = serde::Deserialize::deserialize(&mut de).unwrap();
+    assert_eq!((false, 3), v);
+}
+
+#[test]
+#[wasm_bindgen_test]
+fn test_quote_unsafe() {
+    use json4web::config::Integers;
+    use json4web::Config;
+
+    let config = Config::json4web().integers(Integers::QuoteUnsafe);
+    let j = r#"[1, "9007199254740992", -3, "-4", 5, "340282366920938463463374607431768211455"]"#;
+    let v: (u64, u64, i64, i64, i128, u128) = from_str_with_config(j, config).unwrap();
+    assert_eq!((1, 1 << 53, -3, -4, 5, u128::MAX), v);
+    assert!(from_str_with_config::<u64>("-1", config).is_err());
+    assert!(from_str_with_config::<u64>(r#""x""#, config).is_err());
+
+    let json = json4web::ser::to_string_with_config(&u64::MAX, config).unwrap();
+    assert_eq!(
+        u64::MAX,
+        from_str_with_config::<u64>(&json, config).unwrap()
+    );
+}
+
+#[test]
+#[wasm_bindgen_test]
+fn test_non_finite() {
+    use json4web::config::NonFinite;
+    use json4web::ser::to_string_with_config;
+    use json4web::Config;
+    use serde_derive::Serialize;
+
+    #[derive(Serialize, Deserialize, Debug)]
+    struct Sample {
+        a: f64,
+        b: f32,
+        seq: Vec<f64>,
+        opt: Option<f64>,
+        none: Option<f64>,
+        after: u32,
+    }
+
+    let same = |x: f64, y: f64| x == y || (x.is_nan() && y.is_nan());
+    let sample = Sample {
+        a: f64::NAN,
+        b: f32::NEG_INFINITY,
+        seq: vec![f64::INFINITY, 1.5, f64::NAN],
+        opt: Some(f64::NEG_INFINITY),
+        none: None,
+        after: 7,
+    };
+
+    let config = Config::json4web().non_finite(NonFinite::Strings);
+    let j = to_string_with_config(&sample, config).unwrap();
+    assert_eq!(
+        j,
+        r#"{"a":"NaN","b":"-Infinity","seq":["Infinity",1.5,"NaN"],"opt":"-Infinity","none":null,"after":7}"#
+    );
+    let back: Sample = from_str_with_config(&j, config).unwrap();
+    assert!(same(back.a, sample.a) && same(back.b.into(), sample.b.into()));
+    assert!(back.seq.iter().zip(&sample.seq).all(|(x, y)| same(*x, *y)));
+    assert_eq!(
+        (Some(f64::NEG_INFINITY), None, 7),
+        (back.opt, back.none, back.after)
+    );
+    assert!(from_str_with_config::<f64>(r#""inf""#, config).is_err());
+    assert_eq!(2.5, from_str_with_config::<f64>("2.5", config).unwrap());
+
+    let config = Config::json4web().non_finite(NonFinite::Null);
+    let j = to_string_with_config(&sample, config).unwrap();
+    assert_eq!(
+        j,
+        r#"{"a":null,"b":null,"seq":[null,1.5,null],"opt":null,"none":null,"after":7}"#
+    );
+    let back: Sample = from_str_with_config(&j, config).unwrap();
+    assert!(back.a.is_nan() && back.b.is_nan() && back.seq[0].is_nan());
+    assert_eq!((None, None, 7), (back.opt, back.none, back.after));
+
+    let config = Config::json4web().non_finite(NonFinite::Error);
+    let err = to_string_with_config(&sample, config).unwrap_err();
+    match err.kind() {
+        json4web::Error::NonFiniteFloat => {}
+        other => panic!("{:?}", other),
+    }
+    let finite = Sample {
+        a: 1.0,
+        b: 2.0,
+        seq: vec![3.0],
+        opt: Some(4.0),
+        none: None,
+        after: 5,
+    };
+    let j = to_string_with_config(&finite, config).unwrap();
+    let back: Sample = from_str_with_config(&j, config).unwrap();
+    assert_eq!((1.0, 2.0, 4.0), (back.a, back.b, back.opt.unwrap()));
+    assert!(from_str_with_config::<f64>("null", config).is_err());
+    assert_eq!(
+        None,
+        from_str_with_config::<Option<f64>>("null", config).unwrap()
+    );
+    for err in [
+        from_str_with_config::<f32>("1e39", config).unwrap_err(),
+        from_str_with_config::<f64>("-1e400", config).unwrap_err(),
+        from_str_with_config::<json4web::Value>("[1e400]", config).unwrap_err(),
+    ]
+    .iter()
+    {
+        match err.kind() {
+            json4web::Error::NonFiniteFloat => {}
+            other => panic!("{:?}", other),
+        }
+    }
+    assert_eq!(
+        f64::INFINITY,
+        from_str_with_config::<f64>("1e400", Config::json4web()).unwrap()
+    );
+}
+
+#[test]
+#[wasm_bindgen_test]
+fn test_byte_encodings() {
+    use json4web::config::ByteEncoding;
+    use json4web::ser::to_string_with_config;
+    use json4web::Config;
+    use serde_derive::Serialize;
+
+    #[derive(Serialize, Deserialize, PartialEq, Debug)]
+    struct B {
+        #[serde(with = "serde_bytes")]
+        b: Vec<u8>,
+    }
+    let b = B {
+        b: vec![0xfb, 0xff, 0x01, 0xab],
+    };
+    for (bytes, expected) in [
+        (ByteEncoding::Base64UrlSafe, r#"{"b":"-_8Bqw=="}"#),
+        (ByteEncoding::Base64UrlSafeNoPad, r#"{"b":"-_8Bqw"}"#),
+        (ByteEncoding::Base64Standard, r#"{"b":"+/8Bqw=="}"#),
+        (ByteEncoding::Hex, r#"{"b":"fbff01ab"}"#),
+    ] {
+        let config = Config::json4web().bytes(bytes);
+        let j = to_string_with_config(&b, config).unwrap();
+        assert_eq!(expected, j);
+        assert_eq!(b, from_str_with_config::<B>(&j, config).unwrap());
+    }
+
+    let config = Config::json4web().bytes(ByteEncoding::Hex);
+    assert_eq!(
+        b,
+        from_str_with_config::<B>(r#"{"b":"FBFF01AB"}"#, config).unwrap()
+    );
+    for j in [r#"{"b":"fbf"}"#, r#"{"b":"fbfg"}"#] {
+        let err = from_str_with_config::<B>(j, config).unwrap_err();
+        assert!(matches!(err.kind(), json4web::Error::InvalidHex));
+    }
+
+    let strict = Config::json4web();
+    let err = from_str_with_config::<B>(r#"{"b":"+/8Bqw=="}"#, strict).unwrap_err();
+    assert!(matches!(err.kind(), json4web::Error::Base64Error(_)));
+    let lenient = strict.lenient_base64(true);
+    for j in [
+        r#"{"b":"-_8Bqw=="}"#,
+        r#"{"b":"-_8Bqw"}"#,
+        r#"{"b":"+/8Bqw=="}"#,
+        r#"{"b":"+/8Bqw"}"#,
+    ] {
+        assert_eq!(b, from_str_with_config::<B>(j, lenient).unwrap());
+    }
+    let err = from_str_with_config::<B>(r#"{"b":"+/8B*w=="}"#, lenient).unwrap_err();
+    assert!(matches!(err.kind(), json4web::Error::Base64Error(_)));
+}
+
+#[test]
+#[wasm_bindgen_test]
+fn test_map_keys() {
+    use alloc::collections::BTreeMap;
+    use json4web::Config;
+
+    #[derive(Deserialize, PartialEq, Eq, PartialOrd, Ord, Debug)]
+    enum Color {
+        Red,
+        Blue,
+    }
+
+    #[derive(Deserialize, PartialEq, Eq, PartialOrd, Ord, Debug)]
+    struct Id(u32);
+
+    fn map<K: Ord, V>(entries: Vec<(K, V)>) -> BTreeMap<K, V> {
+        entries.into_iter().collect()
+    }
+
+    test(map(vec![(1u32, 0u8), (20, 1)]), r#"{"1":0,"20":1}"#);
+    test(map(vec![(-1i8, 0u8)]), r#"{ "-1" : 0 }"#);
+    test(map(vec![(u64::MAX, 0u8)]), r#"{"18446744073709551615":0}"#);
+    test(
+        map(vec![(i128::MIN, 0u8)]),
+        r#"{"-170141183460469231731687303715884105728":0}"#,
+    );
+    test(map(vec![(false, 0u8), (true, 1)]), r#"{"0":0,"true":1}"#);
+    test(map(vec![('"', 0u8)]), r#"{"\"":0}"#);
+    test(
+        map(vec![(Color::Red, 0u8), (Color::Blue, 1)]),
+        r#"{"Red":0,"Blue":1}"#,
+    );
+    test(map(vec![(Id(7), 0u8)]), r#"{"7":0}"#);
+
+    let standard = Config::standard();
+    assert_eq!(
+        map(vec![(1u64, 0u8)]),
+        from_str_with_config::<BTreeMap<u64, u8>>(r#"{"1":0}"#, standard).unwrap()
+    );
+    assert!(from_str_with_config::<BTreeMap<bool, u8>>(r#"{"1":0}"#, standard).is_err());
+
+    let err = from_str::<BTreeMap<u8, u8>>(r#"{"1":0,"256":1}"#).unwrap_err();
+    assert!(matches!(err.kind(), json4web::Error::ParseIntError(_)));
+    assert_eq!(Some(12), err.offset());
+    assert!(from_str::<BTreeMap<u32, u8>>(r#"{1:0}"#).is_err());
+}
+
+#[test]
+#[wasm_bindgen_test]
+fn test_any() {
+    use json4web::ser::to_string_with_config;
+    use json4web::Config;
+    use serde::de::DeserializeOwned;
+    use serde::Serialize;
+
+    #[derive(serde_derive::Serialize, Deserialize, PartialEq, Debug)]
+    struct Fields {
+        id: i64,
+        big: u64,
+        small: u8,
+        zero: u32,
+        one: i64,
+        ok: bool,
+        name: String,
+        ratio: f64,
+    }
+
+    #[derive(serde_derive::Serialize, Deserialize, PartialEq, Debug)]
+    #[serde(untagged)]
+    enum Untagged {
+        Fields(Fields),
+        Other(String),
+    }
+
+    #[derive(serde_derive::Serialize, Deserialize, PartialEq, Debug)]
+    #[serde(tag = "type")]
+    enum Internal {
+        Fields(Fields),
+    }
+
+    #[derive(serde_derive::Serialize, Deserialize, PartialEq, Debug)]
+    #[serde(tag = "type", content = "value")]
+    enum Adjacent {
+        Fields(Fields),
+    }
+
+    #[derive(serde_derive::Serialize, Deserialize, PartialEq, Debug)]
+    struct Flattened {
+        #[serde(flatten)]
+        fields: Fields,
+        extra: u32,
+    }
+
+    fn fields() -> Fields {
+        Fields {
+            id: -9_007_199_254_740_993,
+            big: u64::MAX,
+            small: 7,
+            zero: 0,
+            one: 1,
+            ok: true,
+            name: "x".to_owned(),
+            ratio: 0.5,
+        }
+    }
+
+    fn round_trip<T>(v: T, config: Config)
+    where
+        T: Serialize + DeserializeOwned + PartialEq + Debug,
+    {
+        let j = to_string_with_config(&v, config).unwrap();
+        assert_eq!(v, from_str_with_config::<T>(&j, config).unwrap(), "{}", j);
+    }
+
+    let inferred = Config::json4web()
+        .bools(json4web::config::Bools::Literal)
+        .any_quoted_integers(true);
+    for config in [inferred, Config::standard()] {
+        round_trip(Untagged::Fields(fields()), config);
+        round_trip(Untagged::Other("y".to_owned()), config);
+        round_trip(Internal::Fields(fields()), config);
+        round_trip(Adjacent::Fields(fields()), config);
+        round_trip(
+            Flattened {
+                fields: fields(),
+                extra: 3,
+            },
+            config,
+        );
+    }
+
+    let j = r#"{"value":{"id":"-9007199254740993","big":"18446744073709551615","small":7,"zero":0,"one":"1","ok":true,"name":"x","ratio":0.5},"type":"Fields"}"#;
+    assert_eq!(
+        Adjacent::Fields(fields()),
+        from_str_with_config(j, inferred).unwrap()
+    );
+    let numeric = Config::json4web().any_quoted_integers(true);
+    let j = to_string_with_config(&Untagged::Fields(fields()), numeric).unwrap();
+    assert!(from_str_with_config::<Untagged>(&j, numeric).is_err());
+
+    #[derive(Deserialize, PartialEq, Debug)]
+    struct Extra {
+        id: u64,
+        #[serde(flatten)]
+        extra: alloc::collections::BTreeMap<String, u32>,
+    }
+    let extra = from_str_with_config::<Extra>(r#"{"id":"5","a":1,"b":0}"#, numeric).unwrap();
+    assert_eq!(5, extra.id);
+    assert_eq!(
+        vec![("a", 1), ("b", 0)],
+        extra
+            .extra
+            .iter()
+            .map(|(k, v)| (&**k, *v))
+            .collect::<Vec<_>>()
+    );
+
+    #[derive(Deserialize, PartialEq, Debug)]
+    #[serde(untagged)]
+    enum Keyed {
+        A { name: String, n: u32 },
+    }
+    assert!(from_str_with_config::<Keyed>(r#"{"0":"zz","n":3}"#, inferred).is_err());
+    assert_eq!(
+        Keyed::A {
+            name: "zz".to_owned(),
+            n: 3
+        },
+        from_str_with_config(r#"{"name":"zz","n":3}"#, inferred).unwrap()
+    );
+
+    let j = r#"{"type":"Fields","id":"1","big":"2","small":7,"zero":0,"one":1,"ok":1,"name":"x","ratio":0.5}"#;
+    assert!(from_str::<Internal>(j).is_err());
+    let j = r#"{"type":"Fields","id":1,"big":2,"small":7,"zero":0,"one":1,"ok":true,"name":"12","ratio":0.5}"#;
+    match from_str::<Internal>(j).unwrap() {
+        Internal::Fields(f) => assert_eq!((1, 2, "12"), (f.id, f.big, &*f.name)),
+    }
+    assert!(from_str_with_config::<Internal>(j, inferred).is_err());
+
+    use json4web::Value;
+    assert_eq!(
+        Value::from(5u32),
+        from_str_with_config::<Value>(r#""5""#, inferred).unwrap()
+    );
+    for j in [r#""05""#, r#""-""#, r#""5.0""#, r#""99999999999999999999""#] {
+        let v = from_str_with_config::<Value>(j, inferred).unwrap();
+        assert!(matches!(v, Value::String(_)), "{}", j);
+    }
+}
+
+#[test]
+#[wasm_bindgen_test]
+fn test_syntax() {
+    let permissive = |j| -> Result<i32, json4web::Error> {
+        let mut de = Deserializer::new(j).syntax(Syntax::Permissive);
+        let v = serde::Deserialize::deserialize(&mut de)?;
+        de.end()?;
+        Ok(v)
+    };
+    for j in ["1-2", "--3", "01", "-01", "- 1", "-"].iter() {
+        assert!(from_str::<'_, i32>(j).is_err(), "{}", j);
+    }
+    assert!(from_str::<'_, u32>("-0").is_err());
+    test(0i32, "-0");
+    test(10u8, "10");
+    assert!(permissive("01").is_ok());
+    assert!(permissive("1-2").is_err());
+    assert!(permissive("--3").is_err());
+
+    assert!(from_str::<'_, f64>("1.").is_err());
+    assert!(from_str::<'_, f64>("01.5").is_err());
+
+    for j in ["\"a\tb\"", "\"a\nb\"", "\"\u{0}\"", "\"\u{1f}\""].iter() {
+        match from_str::<'_, String>(j).unwrap_err().kind() {
+            json4web::Error::UnexpectedToken(_) => {}
+            other => panic!("{:?}: {:?}", j, other),
+        }
+        assert!(from_str::<'_, serde::de::IgnoredAny>(j).is_err());
+        let mut de = Deserializer::new(j).syntax(Syntax::Permissive);
+        assert!(<String as serde::Deserialize>::deserialize(&mut de).is_ok());
+    }
+    let err = from_str::<'_, String>("\"ab\tc\"").unwrap_err();
+    assert_eq!(Some(3), err.offset());
+    test("a\u{7f}b".to_owned(), "\"a\u{7f}b\"");
+}
+
+#[test]
+#[wasm_bindgen_test]
+fn test_json5() {
+    fn json5<'a, T: serde::Deserialize<'a>>(j: &'a str) -> Result<T, json4web::Error> {
+        let mut de = Deserializer::new(j).syntax(Syntax::Json5);
+        let v = T::deserialize(&mut de)?;
+        de.end()?;
+        Ok(v)
+    }
+
+    #[derive(Deserialize, PartialEq, Debug)]
+    enum E {
+        Newtype(u32),
+    }
+
+    #[derive(Deserialize, PartialEq, Debug)]
+    struct Test {
+        name: String,
+        quote: String,
+        enabled: bool,
+        id: u64,
+        mask: u32,
+        offset: i32,
+        ratio: f64,
+        tags: Vec<String>,
+        e: E,
+    }
+
+    let j = r#"
+        // hand-written
+        {
+            name: 'it\'s "quoted"',
+            quote: "it's",
+            enabled: 1, /* json4web bool */
+            id: '12345678901234567890',
+            mask: 0xFF,
+            offset: +5,
+            ratio: .5,
+            tags: ['a', 'b',],
+            e: { Newtype: 3 },
+            unknown: { a: ['x', /* nested */ 0x10, +.5e1,], 'b': null, },
+        }
+        // trailing comment
+    "#;
+    let expected = Test {
+        name: r#"it's "quoted""#.to_owned(),
+        quote: "it's".to_owned(),
+        enabled: true,
+        id: 12345678901234567890,
+        mask: 255,
+        offset: 5,
+        ratio: 0.5,
+        tags: vec!["a".to_owned(), "b".to_owned()],
+        e: E::Newtype(3),
+    };
+    assert_eq!(expected, json5::<Test>(j).unwrap());
+    assert!(from_str::<'_, Test>(j).is_err());
+
+    assert_eq!(f64::INFINITY, json5::<f64>("Infinity").unwrap());
+    assert_eq!(f64::NEG_INFINITY, json5::<f64>("-Infinity").unwrap());
+    assert!(json5::<f64>("NaN").unwrap().is_nan());
+    assert_eq!(5.0, json5::<f64>("5.").unwrap());
+    assert_eq!(-16i8, json5::<i8>("-0x10").unwrap());
+    assert!(json5::<u8>("0x100").is_err());
+    assert!(json5::<u8>("0x").is_err());
+    assert!(json5::<u8>("01").is_err());
+    assert!(json5::<f64>(".").is_err());
+    assert!(json5::<Vec<u8>>("[1,,]").is_err());
+    assert!(json5::<Vec<u8>>("[,]").is_err());
+    assert!(json5::<String>("'a\"").is_err());
+    assert!(json5::<String>("/ 1").is_err());
+    assert!(json5::<String>("'a' /* open").is_err());
+
+    let mut de = Deserializer::new("Infinity")
+        .syntax(Syntax::Json5)
+        .with_config(json4web::Config::json4web().non_finite(json4web::config::NonFinite::Error));
+    match <f64 as serde::Deserialize>::deserialize(&mut de)
+        .unwrap_err()
+        .kind()
+    {
+        json4web::Error::NonFiniteFloat => {}
+        other => panic!("{:?}", other),
+    }
+
+    let v: json4web::Value = json5("{a: [0x1F, +1, 'x', .5,], $b_2: Infinity, // c\n}").unwrap();
+    assert_eq!(v["a"][0], 31u64);
+    assert_eq!(v["a"][1], 1u64);
+    assert_eq!(v["a"][2], "x");
+    assert_eq!(v["a"][3].as_f64(), Some(0.5));
+    assert_eq!(v["$b_2"], json4web::Value::Null);
+
+    let err = json5::<Test>("{name: 1}").unwrap_err();
+    assert_eq!("name", err.path().unwrap().to_string());
+
+    #[cfg(feature = "std")]
+    {
+        let mut de = Deserializer::from_reader(j.as_bytes()).syntax(Syntax::Json5);
+        assert_eq!(
+            expected,
+            <Test as serde::Deserialize>::deserialize(&mut de).unwrap()
+        );
+        de.end().unwrap();
+    }
+}
+
+#[test]
+#[wasm_bindgen_test]
+fn test_recursion_limit() {
+    let deep = "[".repeat(100_000);
+    for err in [
+        from_str::<'_, json4web::Value>(&deep).unwrap_err(),
+        from_str::<'_, serde::de::IgnoredAny>(&deep).unwrap_err(),
+    ]
+    .iter()
+    {
+        match err.kind() {
+            json4web::Error::RecursionLimitExceeded => {}
+            other => panic!("{:?}", other),
+        }
+        assert_eq!(Some(128), err.offset());
+    }
+    #[cfg(feature = "std")]
+    assert!(json4web::de::from_reader::<_, json4web::Value>(deep.as_bytes()).is_err());
+
+    let nested = format!("{}{}", "[".repeat(128), "]".repeat(128));
+    assert!(from_str::<'_, json4web::Value>(&nested).is_ok());
+    assert!(from_str::<'_, serde::de::IgnoredAny>(&nested).is_ok());
+
+    #[derive(Deserialize, PartialEq, Debug)]
+    enum E {
+        Tuple(Vec<u8>, u8),
+    }
+    let limited = |j, depth| -> Result<E, json4web::Error> {
+        let mut de = Deserializer::new(j).max_depth(depth);
+        serde::Deserialize::deserialize(&mut de)
+    };
+    assert_eq!(
+        E::Tuple(vec![1], 2),
+        limited(r#"{"Tuple":[[1],2]}"#, 3).unwrap()
+    );
+    let err = limited(r#"{"Tuple":[[1],2]}"#, 2).unwrap_err();
+    assert_eq!("Tuple[0]", err.path().unwrap().to_string());
+}
+
+#[test]
+#[wasm_bindgen_test]
+fn test_limits() {
+    use alloc::collections::BTreeMap;
+    use json4web::read::StrRead;
+    use serde_bytes::ByteBuf;
+
+    fn limited<'a, T: serde::Deserialize<'a>>(
+        j: &'a str,
+        limit: impl FnOnce(Deserializer<StrRead<'a>>) -> Deserializer<StrRead<'a>>,
+    ) -> Result<T, json4web::Error> {
+        let mut de = limit(Deserializer::new(j));
+        let v = T::deserialize(&mut de)?;
+        de.end()?;
+        Ok(v)
+    }
+    fn kind<T: Debug>(result: Result<T, json4web::Error>) -> String {
+        format!("{:?}", result.unwrap_err().kind())
+    }
+
+    #[derive(Deserialize, PartialEq, Debug)]
+    struct Test {
+        a: String,
+    }
+
+    let strings = |de: Deserializer<_>| de.max_string_len(3);
+    assert_eq!("abc", limited::<String>(r#""abc""#, strings).unwrap());
+    assert_eq!(
+        "StringTooLong",
+        kind(limited::<String>(r#""abcd""#, strings))
+    );
+    assert_eq!(
+        "StringTooLong",
+        kind(limited::<String>(r#""\u00e9""#, strings))
+    );
+    assert_eq!(
+        "StringTooLong",
+        kind(limited::<String>("\"\u{e9}\u{e9}\"", strings))
+    );
+    assert_eq!(
+        "StringTooLong",
+        kind(limited::<Test>(r#"{"a":"b","long":1}"#, strings))
+    );
+    assert_eq!(
+        "StringTooLong",
+        kind(limited::<Test>(r#"{"a":"b","c":"long"}"#, strings))
+    );
+    let err = limited::<Test>(r#"{"a":"long"}"#, strings).unwrap_err();
+    assert_eq!(Some(9), err.offset());
+    let j = "{\"b\":\"\u{e9}\u{e9}\u{e9}\u{e9}\",\"a\":\"x\"}";
+    let err = limited::<Test>(j, |de| de.max_string_len(1)).unwrap_err();
+    assert_eq!("StringTooLong", format!("{:?}", err.kind()));
+    assert_eq!(Some(8), err.offset());
+    assert_eq!(
+        "StringTooLong",
+        kind(limited::<Test>("{long: 'b'}", |de| de
+            .syntax(Syntax::Json5)
+            .max_string_len(3)))
+    );
+    #[cfg(feature = "std")]
+    {
+        let mut de = Deserializer::from_reader(&b"\"abcd\""[..]).max_string_len(3);
+        assert!(<String as serde::Deserialize>::deserialize(&mut de).is_err());
+        let mut de = Deserializer::from_reader(j.as_bytes()).max_string_len(1);
+        assert!(<Test as serde::Deserialize>::deserialize(&mut de).is_err());
+    }
+
+    let arrays = |de: Deserializer<_>| de.max_array_len(2);
+    assert_eq!(vec![1, 2], limited::<Vec<u8>>("[1,2]", arrays).unwrap());
+    assert_eq!(
+        "TooManyElements",
+        kind(limited::<Vec<u8>>("[1,2,3]", arrays))
+    );
+    assert_eq!(
+        "TooManyElements",
+        kind(limited::<json4web::Value>("[[1,2,3]]", arrays))
+    );
+    assert_eq!(
+        "TooManyElements",
+        kind(limited::<Test>(r#"{"a":"b","c":[1,2,3]}"#, arrays))
+    );
+
+    let objects = |de: Deserializer<_>| de.max_object_len(1);
+    assert_eq!(
+        1,
+        limited::<BTreeMap<String, u8>>(r#"{"a":1}"#, objects)
+            .unwrap()
+            .len()
+    );
+    assert_eq!(
+        "TooManyKeys",
+        kind(limited::<BTreeMap<String, u8>>(r#"{"a":1,"b":2}"#, objects))
+    );
+    assert_eq!(
+        "TooManyKeys",
+        kind(limited::<Test>(r#"{"a":"b","c":{"d":1,"e":2}}"#, objects))
+    );
+
+    let numbers = |de: Deserializer<_>| de.max_number_len(3);
+    assert_eq!(-12, limited::<i32>("-12", numbers).unwrap());
+    assert_eq!("NumberTooLong", kind(limited::<i32>("1234", numbers)));
+    assert_eq!("NumberTooLong", kind(limited::<f64>("-1.5", numbers)));
+    assert_eq!(
+        "NumberTooLong",
+        kind(limited::<json4web::Value>("1e100", numbers))
+    );
+    assert_eq!(
+        "NumberTooLong",
+        kind(limited::<Test>(r#"{"a":"b","c":1000}"#, numbers))
+    );
+    assert_eq!(
+        "NumberTooLong",
+        kind(limited::<u32>("0x1000", |de| de
+            .syntax(Syntax::Json5)
+            .max_number_len(5)))
+    );
+    assert_eq!(123u64, limited::<u64>(r#""123""#, numbers).unwrap());
+    assert_eq!(
+        "NumberTooLong",
+        kind(limited::<u64>(r#""12345""#, |de| de.max_number_len(4)))
+    );
+    assert_eq!(
+        "NumberTooLong",
+        kind(limited::<i128>(r#""-1234""#, numbers))
+    );
+    let inferred = |de: Deserializer<_>| {
+        de.with_config(json4web::Config::json4web().any_quoted_integers(true))
+            .max_number_len(3)
+    };
+    assert_eq!(
+        "NumberTooLong",
+        kind(limited::<json4web::Value>(r#"["1234"]"#, inferred))
+    );
+    assert_eq!(
+        json4web::json!(["abcd"]),
+        limited::<json4web::Value>(r#"["abcd"]"#, inferred).unwrap()
+    );
+
+    let bytes = |de: Deserializer<_>| de.max_bytes_len(3);
+    assert_eq!(
+        &[1, 2, 3],
+        &limited::<ByteBuf>(r#""AQID""#, bytes).unwrap()[..]
+    );
+    assert_eq!(
+        "BytesTooLong",
+        kind(limited::<ByteBuf>(r#""AQIDBA==""#, bytes))
+    );
+    let hex = |de: Deserializer<_>| {
+        de.max_bytes_len(3)
+            .with_config(json4web::Config::json4web().bytes(json4web::config::ByteEncoding::Hex))
+    };
+    assert_eq!(
+        &[1, 2, 3],
+        &limited::<ByteBuf>(r#""010203""#, hex).unwrap()[..]
+    );
+    assert_eq!(
+        "BytesTooLong",
+        kind(limited::<ByteBuf>(r#""01020304""#, hex))
+    );
 }
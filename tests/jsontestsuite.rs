@@ -0,0 +1,277 @@
+#![no_std]
+
+#[macro_use]
+extern crate wasm_bindgen_test;
+
+use json4web::de::from_slice;
+use json4web::Value;
+use serde::de::IgnoredAny;
+
+/// The named files of the vendored JSONTestSuite corpus, see `data/jsontestsuite/README.md`.
+macro_rules! corpus {
+    ($($file:literal,)*) => {
+        &[$(($file, include_bytes!(concat!("data/jsontestsuite/", $file)) as &[u8]),)*]
+    };
+    ($($file:literal => $outcome:expr,)*) => {
+        &[$(($file, include_bytes!(concat!("data/jsontestsuite/", $file)) as &[u8], $outcome),)*]
+    };
+}
+
+/// Whether a file parses into a `Value` and whether it parses when skipped.
+type Outcome = [bool; 2];
+
+const ACCEPT: Outcome = [true, true];
+const REJECT: Outcome = [false, false];
+/// Skipping a string checks its escapes but not how surrogates pair up.
+const SKIP_ONLY: Outcome = [false, true];
+
+/// `y_` files, which are valid JSON.
+const ACCEPTED: &[(&str, &[u8])] = corpus![
+    "y_array_arraysWithSpaces.json",
+    "y_array_empty-string.json",
+    "y_array_empty.json",
+    "y_array_ending_with_newline.json",
+    "y_array_false.json",
+    "y_array_heterogeneous.json",
+    "y_array_null.json",
+    "y_array_with_1_and_newline.json",
+    "y_array_with_leading_space.json",
+    "y_array_with_several_null.json",
+    "y_array_with_trailing_space.json",
+    "y_number.json",
+    "y_number_0e+1.json",
+    "y_number_0e1.json",
+    "y_number_after_space.json",
+    "y_number_double_close_to_zero.json",
+    "y_number_int_with_exp.json",
+    "y_number_minus_zero.json",
+    "y_number_negative_int.json",
+    "y_number_negative_one.json",
+    "y_number_negative_zero.json",
+    "y_number_real_capital_e.json",
+    "y_number_real_capital_e_neg_exp.json",
+    "y_number_real_capital_e_pos_exp.json",
+    "y_number_real_exponent.json",
+    "y_number_real_fraction_exponent.json",
+    "y_number_real_neg_exp.json",
+    "y_number_real_pos_exponent.json",
+    "y_number_simple_int.json",
+    "y_number_simple_real.json",
+    "y_object.json",
+    "y_object_basic.json",
+    "y_object_duplicated_key.json",
+    "y_object_empty.json",
+    "y_object_empty_key.json",
+    "y_object_escaped_null_in_key.json",
+    "y_object_extreme_numbers.json",
+    "y_object_simple.json",
+    "y_object_with_newlines.json",
+    "y_string_1_2_3_bytes_UTF-8_sequences.json",
+    "y_string_accepted_surrogate_pair.json",
+    "y_string_allowed_escapes.json",
+    "y_string_backslash_and_u_escaped_zero.json",
+    "y_string_comments.json",
+    "y_string_escaped_control_character.json",
+    "y_string_in_array.json",
+    "y_string_nonCharacterInUTF-8_U+FFFF.json",
+    "y_string_space.json",
+    "y_string_unicode.json",
+    "y_string_utf8.json",
+    "y_string_with_del_character.json",
+    "y_structure_lonely_false.json",
+    "y_structure_lonely_int.json",
+    "y_structure_lonely_negative_real.json",
+    "y_structure_lonely_null.json",
+    "y_structure_lonely_string.json",
+    "y_structure_lonely_true.json",
+    "y_structure_string_empty.json",
+    "y_structure_trailing_newline.json",
+    "y_structure_true_in_array.json",
+    "y_structure_whitespace_array.json",
+];
+
+/// `n_` files, which are not JSON.
+const REJECTED: &[(&str, &[u8])] = corpus![
+    "n_array_1_true_without_comma.json",
+    "n_array_comma_after_close.json",
+    "n_array_comma_and_number.json",
+    "n_array_double_comma.json",
+    "n_array_extra_close.json",
+    "n_array_extra_comma.json",
+    "n_array_incomplete.json",
+    "n_array_inner_array_no_comma.json",
+    "n_array_just_comma.json",
+    "n_array_just_minus.json",
+    "n_array_missing_value.json",
+    "n_array_number_and_comma.json",
+    "n_array_unclosed.json",
+    "n_incomplete_false.json",
+    "n_incomplete_null.json",
+    "n_incomplete_true.json",
+    "n_number_++.json",
+    "n_number_+1.json",
+    "n_number_-01.json",
+    "n_number_-1.0..json",
+    "n_number_-2..json",
+    "n_number_.-1.json",
+    "n_number_.2e-3.json",
+    "n_number_0.1.2.json",
+    "n_number_0.e1.json",
+    "n_number_0_capital_E.json",
+    "n_number_0e.json",
+    "n_number_1.0e+.json",
+    "n_number_1.0e.json",
+    "n_number_1_000.json",
+    "n_number_2.e3.json",
+    "n_number_9.e+.json",
+    "n_number_Inf.json",
+    "n_number_NaN.json",
+    "n_number_expression.json",
+    "n_number_hex_1_digit.json",
+    "n_number_infinity.json",
+    "n_number_minus_infinity.json",
+    "n_number_minus_sign_with_trailing_garbage.json",
+    "n_number_neg_int_starting_with_zero.json",
+    "n_number_neg_real_without_int_part.json",
+    "n_number_real_without_fractional_part.json",
+    "n_number_starting_with_dot.json",
+    "n_number_with_leading_zero.json",
+    "n_object_bad_value.json",
+    "n_object_comma_instead_of_colon.json",
+    "n_object_missing_colon.json",
+    "n_object_missing_key.json",
+    "n_object_missing_value.json",
+    "n_object_no-colon.json",
+    "n_object_non_string_key.json",
+    "n_object_single_quote.json",
+    "n_object_trailing_comma.json",
+    "n_object_trailing_comment.json",
+    "n_object_trailing_comment_slash_open.json",
+    "n_object_unquoted_key.json",
+    "n_single_space.json",
+    "n_string_escape_x.json",
+    "n_string_escaped_emoji.json",
+    "n_string_incomplete_escape.json",
+    "n_string_incomplete_surrogate_escape_invalid.json",
+    "n_string_invalid_backslash_esc.json",
+    "n_string_invalid_unicode_escape.json",
+    "n_string_no_quotes_with_bad_escape.json",
+    "n_string_single_doublequote.json",
+    "n_string_single_quote.json",
+    "n_string_unescaped_ctrl_char.json",
+    "n_string_unescaped_newline.json",
+    "n_string_unescaped_tab.json",
+    "n_string_unicode_CapitalU.json",
+    "n_string_with_trailing_garbage.json",
+    "n_structure_100000_opening_arrays.json",
+    "n_structure_UTF8_BOM_no_data.json",
+    "n_structure_angle_bracket_..json",
+    "n_structure_close_unopened_array.json",
+    "n_structure_double_array.json",
+    "n_structure_end_array.json",
+    "n_structure_lone-open-bracket.json",
+    "n_structure_no_data.json",
+    "n_structure_null-byte-outside-string.json",
+    "n_structure_object_with_comment.json",
+    "n_structure_trailing_#.json",
+    "n_structure_unclosed_object.json",
+    "n_structure_whitespace_formfeed.json",
+];
+
+/// `i_` files, which the RFC leaves to the parser, with what json4web does.
+const IMPLEMENTATION_DEFINED: &[(&str, &[u8], Outcome)] = corpus![
+    // Out of range numbers become infinity, zero or a float.
+    "i_number_double_huge_neg_exp.json" => ACCEPT,
+    "i_number_huge_exp.json" => ACCEPT,
+    "i_number_neg_int_huge_exp.json" => ACCEPT,
+    "i_number_pos_double_huge_exp.json" => ACCEPT,
+    "i_number_real_neg_overflow.json" => ACCEPT,
+    "i_number_real_pos_overflow.json" => ACCEPT,
+    "i_number_real_underflow.json" => ACCEPT,
+    "i_number_too_big_neg_int.json" => ACCEPT,
+    "i_number_too_big_pos_int.json" => ACCEPT,
+    "i_number_very_big_negative_int.json" => ACCEPT,
+    // Lone surrogates are rejected unless `LoneSurrogates::Replace` is set.
+    "i_object_key_lone_2nd_surrogate.json" => SKIP_ONLY,
+    "i_string_1st_surrogate_but_2nd_missing.json" => SKIP_ONLY,
+    "i_string_1st_valid_surrogate_2nd_invalid.json" => SKIP_ONLY,
+    "i_string_incomplete_surrogate_and_escape_valid.json" => SKIP_ONLY,
+    "i_string_invalid_lonely_surrogate.json" => SKIP_ONLY,
+    // Input must be UTF-8.
+    "i_string_UTF-8_invalid_sequence.json" => REJECT,
+    "i_string_invalid_utf-8.json" => REJECT,
+    "i_string_lone_utf8_continuation_byte.json" => REJECT,
+    "i_string_not_in_unicode_range.json" => REJECT,
+    "i_string_truncated-utf-8.json" => REJECT,
+    // Deeper than the default `max_depth` of 128.
+    "i_structure_500_nested_arrays.json" => REJECT,
+    // A byte order mark is not whitespace.
+    "i_structure_UTF-8_BOM_empty_object.json" => REJECT,
+];
+
+/// Parses `input` both into a `Value` and by skipping it.
+fn parse(input: &[u8]) -> Outcome {
+    [
+        from_slice::<Value>(input).is_ok(),
+        from_slice::<IgnoredAny>(input).is_ok(),
+    ]
+}
+
+#[cfg(feature = "std")]
+fn parse_reader(input: &[u8]) -> Outcome {
+    [
+        json4web::de::from_reader::<_, Value>(input).is_ok(),
+        json4web::de::from_reader::<_, IgnoredAny>(input).is_ok(),
+    ]
+}
+
+#[test]
+#[wasm_bindgen_test]
+fn test_accepted() {
+    for (file, input) in ACCEPTED {
+        assert_eq!(ACCEPT, parse(input), "{}", file);
+        #[cfg(feature = "std")]
+        assert_eq!(ACCEPT, parse_reader(input), "{}", file);
+    }
+}
+
+#[test]
+#[wasm_bindgen_test]
+fn test_rejected() {
+    for (file, input) in REJECTED {
+        assert_eq!(REJECT, parse(input), "{}", file);
+        #[cfg(feature = "std")]
+        assert_eq!(REJECT, parse_reader(input), "{}", file);
+    }
+}
+
+#[test]
+#[wasm_bindgen_test]
+fn test_implementation_defined() {
+    for (file, input, outcome) in IMPLEMENTATION_DEFINED {
+        assert_eq!(*outcome, parse(input), "{}", file);
+        #[cfg(feature = "std")]
+        assert_eq!(*outcome, parse_reader(input), "{}", file);
+    }
+}
+
+/// Every file of the corpus must be listed above, so none is left out silently.
+#[test]
+#[cfg(feature = "std")]
+fn test_corpus_is_listed() {
+    extern crate std;
+
+    let dir = concat!(env!("CARGO_MANIFEST_DIR"), "/tests/data/jsontestsuite");
+    let listed = ACCEPTED
+        .iter()
+        .chain(REJECTED)
+        .map(|(file, _)| *file)
+        .chain(IMPLEMENTATION_DEFINED.iter().map(|(file, _, _)| *file));
+    let listed: std::collections::BTreeSet<_> = listed.collect();
+    for entry in std::fs::read_dir(dir).unwrap() {
+        let name = entry.unwrap().file_name().into_string().unwrap();
+        if name.ends_with(".json") {
+            assert!(listed.contains(&*name), "{} is not listed", name);
+        }
+    }
+}
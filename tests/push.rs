@@ -0,0 +1,217 @@
+#![no_std]
+#[macro_use]
+extern crate alloc;
+#[macro_use]
+extern crate wasm_bindgen_test;
+
+use alloc::borrow::ToOwned;
+use alloc::string::{String, ToString};
+use alloc::vec::Vec;
+use json4web::push::PushParser;
+use json4web::{json, Error, Value};
+use serde_derive::Deserialize;
+
+#[cfg(test)]
+fn drain(parser: &mut PushParser) -> Vec<Value> {
+    let mut values = Vec::new();
+    while let Some(value) = parser.next_value() {
+        values.push(value.unwrap());
+    }
+    values
+}
+
+#[test]
+#[wasm_bindgen_test]
+fn test_values() {
+    let j = "{\"a\":\"x\\\"}y\"} 12\n[1,[2]]\"caf\u{e9}\"true";
+    let mut parser = PushParser::new();
+    let mut values = Vec::new();
+    for b in j.as_bytes() {
+        parser.feed(&[*b]);
+        values.extend(drain(&mut parser));
+    }
+    assert_eq!(
+        values,
+        vec![
+            json!({"a": "x\"}y"}),
+            json!(12),
+            json!([1, [2]]),
+            json!("caf\u{e9}")
+        ]
+    );
+    parser.finish();
+    assert_eq!(drain(&mut parser), vec![json!(true)]);
+    assert!(parser.next_value::<Value>().is_none());
+}
+
+/// One chunk of 200k values, as a large response might arrive in a single read.
+#[cfg(feature = "std")]
+fn large_chunk() -> String {
+    let mut j = String::new();
+    for id in 0..200_000 {
+        j.push_str(&format!("{{\"id\":\"{}\",\"name\":\"n\"}}\n", id));
+    }
+    j
+}
+
+/// Returning a value must not shift the rest of the buffer, which made this
+/// take seconds instead of well under one.
+#[test]
+#[cfg(feature = "std")]
+fn test_large_chunk() {
+    extern crate std;
+
+    #[derive(Deserialize)]
+    struct Event {
+        id: u64,
+    }
+
+    let j = large_chunk();
+    assert!(j.len() > 2_000_000);
+    let started = std::time::Instant::now();
+    let mut parser = PushParser::new();
+    parser.feed(j.as_bytes());
+    let mut count = 0;
+    while let Some(event) = parser.next_value::<Event>() {
+        assert_eq!(count, event.unwrap().id);
+        count += 1;
+    }
+    assert_eq!(200_000, count);
+    assert!(started.elapsed() < std::time::Duration::from_secs(8));
+    parser.feed(b"{\"id\":\"7\"}");
+    parser.finish();
+    assert_eq!(7, parser.next_value::<Event>().unwrap().unwrap().id);
+}
+
+#[test]
+#[wasm_bindgen_test]
+fn test_array_elements() {
+    #[derive(Deserialize, PartialEq, Debug)]
+    struct Item {
+        name: String,
+        price: u32,
+    }
+
+    let mut parser = PushParser::array_elements();
+    parser.feed(b" [ {\"name\":\"a\",\"pri");
+    assert!(parser.next_value::<Item>().is_none());
+    parser.feed(b"ce\":1} ,\n {\"name\":\"b\",\"price\":\"x\"}, {\"name\":\"c\",");
+    let item = parser.next_value::<Item>().unwrap().unwrap();
+    assert_eq!(
+        item,
+        Item {
+            name: "a".to_owned(),
+            price: 1
+        }
+    );
+    let err = parser.next_value::<Item>().unwrap().unwrap_err();
+    assert_eq!("[1].price", err.path().unwrap().to_string());
+    assert_eq!(Some(49), err.offset());
+    assert_eq!(Some(2), err.line());
+    assert_eq!(Some(22), err.column());
+    assert!(parser.next_value::<Item>().is_none());
+    parser.feed(b"\"price\":3}]  ");
+    parser.finish();
+    assert_eq!(parser.next_value::<Item>().unwrap().unwrap().price, 3);
+    assert!(parser.next_value::<Item>().is_none());
+
+    let mut parser = PushParser::array_elements();
+    parser.feed(b"[1,2");
+    assert_eq!(drain(&mut parser), vec![json!(1)]);
+    parser.feed(b"3]");
+    assert_eq!(drain(&mut parser), vec![json!(23)]);
+
+    let mut parser = PushParser::array_elements();
+    parser.feed(b"[ ]");
+    parser.finish();
+    assert!(parser.next_value::<Value>().is_none());
+}
+
+#[test]
+#[wasm_bindgen_test]
+fn test_errors() {
+    let kind = |array: bool, j: &str| {
+        let mut parser = if array {
+            PushParser::array_elements()
+        } else {
+            PushParser::new()
+        };
+        parser.feed(j.as_bytes());
+        parser.finish();
+        loop {
+            match parser.next_value::<Value>() {
+                Some(Ok(_)) => continue,
+                Some(Err(err)) => {
+                    assert!(parser.next_value::<Value>().is_none());
+                    return format!("{:?}", err.kind());
+                }
+                None => return "none".to_owned(),
+            }
+        }
+    };
+    assert_eq!(
+        kind(false, "[1}"),
+        format!("{:?}", Error::UnexpectedToken('}'))
+    );
+    assert_eq!(
+        kind(false, "1 ]"),
+        format!("{:?}", Error::UnexpectedToken(']'))
+    );
+    assert_eq!(
+        kind(false, "{\"a\":[1"),
+        format!("{:?}", Error::UnexpectedEnd)
+    );
+    assert_eq!(kind(false, "\"abc"), format!("{:?}", Error::UnexpectedEnd));
+    assert_eq!(
+        kind(true, "{}"),
+        format!("{:?}", Error::UnexpectedToken('{'))
+    );
+    assert_eq!(
+        kind(true, "[1,,2]"),
+        format!("{:?}", Error::UnexpectedToken(','))
+    );
+    assert_eq!(
+        kind(true, "[1 2]"),
+        format!("{:?}", Error::UnexpectedToken('2'))
+    );
+    assert_eq!(
+        kind(true, "[1,]"),
+        format!("{:?}", Error::UnexpectedToken(']'))
+    );
+    assert_eq!(
+        kind(true, "[1] 2"),
+        format!("{:?}", Error::TrailingCharacters)
+    );
+    assert_eq!(kind(true, "[1"), format!("{:?}", Error::UnexpectedEnd));
+    assert_eq!(kind(true, ""), format!("{:?}", Error::UnexpectedEnd));
+    assert_eq!(kind(false, " "), "none");
+    assert_eq!(
+        kind(false, "1\"a\""),
+        format!("{:?}", Error::UnexpectedToken('"'))
+    );
+    assert_eq!(
+        kind(false, "truefalse"),
+        format!("{:?}", Error::TrailingCharacters)
+    );
+
+    let mut parser = PushParser::new();
+    parser.feed(b"1\n  [}");
+    parser.next_value::<Value>().unwrap().unwrap();
+    let err = parser.next_value::<Value>().unwrap().unwrap_err();
+    assert_eq!(
+        (Some(5), Some(2), Some(4)),
+        (err.offset(), err.line(), err.column())
+    );
+}
+
+#[test]
+#[wasm_bindgen_test]
+fn test_config() {
+    let mut parser = PushParser::new().with_config(json4web::Config::standard());
+    parser.feed(b"18446744073709551615 true ");
+    assert_eq!(
+        Some(u64::MAX),
+        parser.next_value::<u64>().map(Result::unwrap)
+    );
+    assert_eq!(Some(true), parser.next_value::<bool>().map(Result::unwrap));
+}
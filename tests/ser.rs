@@ -4,8 +4,10 @@ extern crate alloc;
 #[macro_use]
 extern crate wasm_bindgen_test;
 
+use alloc::string::ToString;
 use alloc::vec::Vec;
 use json4web::ser::*;
+#[allow(deprecated)]
 use serde::serde_if_integer128;
 use serde_derive::Serialize;
 
@@ -87,10 +89,13 @@ fn test_string() {
     let expected = r#""\"\\\/\b\f\n\r\t""#;
     test(s, expected);
     test("𐎅", "\"𐎅\"");
+    test("\x00\x01\x0b\x1f", r#""\u0000\u0001\u000b\u001f""#);
+    test("\u{108}\u{10a}\u{10d}", "\"\u{108}\u{10a}\u{10d}\"");
 }
 
 #[test]
 #[wasm_bindgen_test]
+#[allow(deprecated, clippy::legacy_numeric_constants)]
 fn test_number() {
     test(123u8, r#"123"#);
     test(12345u16, r#"12345"#);
@@ -115,3 +120,345 @@ fn test_number() {
 fn test_null() {
     test((), r#"null"#);
 }
+
+#[test]
+#[wasm_bindgen_test]
+fn test_pretty() {
+    #[derive(Serialize)]
+    struct Inner {
+        a: u32,
+    }
+    #[derive(Serialize)]
+    enum E {
+        Newtype(u32),
+        Tuple(u32, u32),
+        Struct { a: u32 },
+        EmptyTuple(),
+    }
+    #[derive(Serialize)]
+    struct Test {
+        int: u32,
+        seq: Vec<&'static str>,
+        empty_seq: Vec<u32>,
+        empty_map: alloc::collections::BTreeMap<u32, u32>,
+        inner: Inner,
+        enums: Vec<E>,
+    }
+
+    let t = Test {
+        int: 1,
+        seq: vec!["a", "b"],
+        empty_seq: vec![],
+        empty_map: Default::default(),
+        inner: Inner { a: 2 },
+        enums: vec![
+            E::Newtype(1),
+            E::Tuple(1, 2),
+            E::Struct { a: 1 },
+            E::EmptyTuple(),
+        ],
+    };
+    let expected = r#"{
+  "int": 1,
+  "seq": [
+    "a",
+    "b"
+  ],
+  "empty_seq": [],
+  "empty_map": {},
+  "inner": {
+    "a": 2
+  },
+  "enums": [
+    {
+      "Newtype": 1
+    },
+    {
+      "Tuple": [
+        1,
+        2
+      ]
+    },
+    {
+      "Struct": {
+        "a": 1
+      }
+    },
+    {
+      "EmptyTuple": []
+    }
+  ]
+}"#;
+    assert_eq!(to_string_pretty(&t).unwrap(), expected);
+    assert_eq!(
+        to_string_pretty_with_indent(&vec![vec![1]], "\t").unwrap(),
+        "[\n\t[\n\t\t1\n\t]\n]"
+    );
+    assert_eq!(to_string_pretty(&1).unwrap(), "1");
+    let compact = to_string(&t).unwrap();
+    assert_eq!(
+        json4web::de::from_str::<json4web::Value>(&compact).unwrap(),
+        json4web::de::from_str::<json4web::Value>(expected).unwrap()
+    );
+}
+
+#[test]
+#[wasm_bindgen_test]
+fn test_fmt() {
+    use alloc::string::String;
+    use core::fmt::Write;
+
+    let mut s = String::new();
+    to_fmt(&mut s, &vec![1, 2]).unwrap();
+    to_fmt(&mut s, "a").unwrap();
+    assert_eq!(s, r#"[1,2]"a""#);
+
+    let mut s = String::new();
+    write!(s, "body={};", Display(&(1u64, true))).unwrap();
+    assert_eq!(s, r#"body=["1",1];"#);
+    assert_eq!(format!("{}", Display("x/y")), r#""x\/y""#);
+
+    let mut serializer = Serializer::pretty(FmtWrite(String::new()), " ");
+    serde::Serialize::serialize(&[1], &mut serializer).unwrap();
+    assert_eq!(serializer.into_inner().0, "[\n 1\n]");
+}
+
+#[cfg(feature = "std")]
+#[test]
+#[wasm_bindgen_test]
+fn test_writer() {
+    extern crate std;
+
+    let mut out = Vec::new();
+    to_writer(&mut out, &vec!["a", "b"]).unwrap();
+    assert_eq!(out, br#"["a","b"]"#);
+
+    let mut out = Vec::new();
+    to_writer_pretty(&mut out, &vec![1]).unwrap();
+    assert_eq!(out, b"[\n  1\n]");
+
+    struct Failing;
+    impl std::io::Write for Failing {
+        fn write(&mut self, _buf: &[u8]) -> std::io::Result<usize> {
+            Err(std::io::Error::other("closed"))
+        }
+        fn flush(&mut self) -> std::io::Result<()> {
+            Ok(())
+        }
+    }
+    match to_writer(Failing, &1) {
+        Err(json4web::Error::Io(e)) => assert_eq!(e.kind(), std::io::ErrorKind::Other),
+        other => panic!("{:?}", other),
+    }
+}
+
+#[test]
+#[wasm_bindgen_test]
+fn test_json_lines() {
+    #[derive(Serialize)]
+    struct Record<'a> {
+        msg: &'a str,
+        id: u64,
+    }
+
+    let mut lines = JsonLinesWriter::new(Vec::new());
+    lines.write(&Record { msg: "a\nb", id: 1 }).unwrap();
+    lines.write(&vec![true, false]).unwrap();
+    lines.write("\r\n").unwrap();
+    let out = lines.into_inner();
+    assert_eq!(
+        core::str::from_utf8(&out).unwrap(),
+        "{\"msg\":\"a\\nb\",\"id\":\"1\"}\n[1,0]\n\"\\r\\n\"\n"
+    );
+}
+
+#[test]
+#[cfg(feature = "std")]
+fn test_json_lines_flush() {
+    extern crate std;
+    use std::io;
+
+    #[derive(Default)]
+    struct Sink {
+        data: Vec<u8>,
+        flushed: Vec<usize>,
+    }
+    impl io::Write for Sink {
+        fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+            self.data.extend_from_slice(buf);
+            Ok(buf.len())
+        }
+        fn flush(&mut self) -> io::Result<()> {
+            self.flushed.push(self.data.len());
+            Ok(())
+        }
+    }
+
+    let mut lines = JsonLinesWriter::new(IoWrite(Sink::default())).flush_each_line(true);
+    lines.write(&1).unwrap();
+    lines.write(&[2, 3]).unwrap();
+    let sink = lines.into_inner().0;
+    assert_eq!(sink.data, b"1\n[2,3]\n");
+    assert_eq!(sink.flushed, vec![2, 8]);
+
+    let mut lines = JsonLinesWriter::new(IoWrite(Sink::default()));
+    lines.write(&1).unwrap();
+    assert!(lines.into_inner().0.flushed.is_empty());
+}
+
+#[test]
+#[wasm_bindgen_test]
+fn test_standard() {
+    use json4web::config::{Bools, Integers};
+    use json4web::Config;
+
+    #[derive(Serialize)]
+    struct Test {
+        ok: bool,
+        small: u32,
+        id: u64,
+        delta: i64,
+        big: u128,
+    }
+
+    let t = Test {
+        ok: true,
+        small: 7,
+        id: u64::MAX,
+        delta: -5,
+        big: 1 << 100,
+    };
+    assert_eq!(
+        to_string_with_config(&t, Config::standard()).unwrap(),
+        r#"{"ok":true,"small":7,"id":18446744073709551615,"delta":-5,"big":1267650600228229401496703205376}"#
+    );
+    assert_eq!(
+        to_string_with_config(&t, Config::json4web()).unwrap(),
+        to_string(&t).unwrap()
+    );
+    assert_eq!(
+        to_string_with_config(&(true, 1u64), Config::json4web().bools(Bools::Literal)).unwrap(),
+        r#"[true,"1"]"#
+    );
+    assert_eq!(
+        to_string_with_config(&(true, 1u64), Config::json4web().integers(Integers::Bare)).unwrap(),
+        r#"[1,1]"#
+    );
+
+    let mut serializer = Serializer::pretty(Vec::new(), " ").with_config(Config::standard());
+    serde::Serialize::serialize(&vec![false], &mut serializer).unwrap();
+    assert_eq!(serializer.into_inner(), b"[\n false\n]");
+
+    let mut lines = JsonLinesWriter::new(Vec::new()).with_config(Config::standard());
+    lines.write(&(false, -1i64)).unwrap();
+    assert_eq!(lines.into_inner(), b"[false,-1]\n");
+}
+
+#[test]
+#[wasm_bindgen_test]
+fn test_quote_unsafe() {
+    use json4web::config::Integers;
+    use json4web::Config;
+
+    fn check<T: serde::Serialize>(v: T, expected: &str) {
+        let config = Config::json4web().integers(Integers::QuoteUnsafe);
+        assert_eq!(to_string_with_config(&v, config).unwrap(), expected);
+    }
+
+    let max = (1u64 << 53) - 1;
+    check(0u64, "0");
+    check(max, "9007199254740991");
+    check(max + 1, r#""9007199254740992""#);
+    check(-(max as i64), "-9007199254740991");
+    check(-(max as i64) - 1, r#""-9007199254740992""#);
+    check(i64::MIN, r#""-9223372036854775808""#);
+    check(u128::from(max), "9007199254740991");
+    check(u128::MAX, r#""340282366920938463463374607431768211455""#);
+    check(-i128::from(max), "-9007199254740991");
+    check(i128::MIN, r#""-170141183460469231731687303715884105728""#);
+    check(7u32, "7");
+}
+
+#[test]
+#[wasm_bindgen_test]
+fn test_map_keys() {
+    use alloc::collections::BTreeMap;
+    use json4web::Config;
+
+    #[derive(Serialize, PartialEq, Eq, PartialOrd, Ord)]
+    enum Color {
+        Red,
+        Blue,
+    }
+
+    #[derive(Serialize, PartialEq, Eq, PartialOrd, Ord)]
+    struct Id(u32);
+
+    fn map<K: Ord, V>(entries: Vec<(K, V)>) -> BTreeMap<K, V> {
+        entries.into_iter().collect()
+    }
+
+    test(map(vec![(1u32, "a"), (20, "b")]), r#"{"1":"a","20":"b"}"#);
+    test(map(vec![(-1i8, 0u8)]), r#"{"-1":0}"#);
+    test(map(vec![(u64::MAX, 0u8)]), r#"{"18446744073709551615":0}"#);
+    test(
+        map(vec![(i128::MIN, 0u8)]),
+        r#"{"-170141183460469231731687303715884105728":0}"#,
+    );
+    test(map(vec![(false, 0u8), (true, 1)]), r#"{"0":0,"1":1}"#);
+    test(map(vec![('"', 0u8)]), r#"{"\"":0}"#);
+    test(
+        map(vec![(Color::Red, 0u8), (Color::Blue, 1)]),
+        r#"{"Red":0,"Blue":1}"#,
+    );
+    test(map(vec![(Id(7), 0u8)]), r#"{"7":0}"#);
+
+    let bools = map(vec![(false, 0u8), (true, 1)]);
+    assert_eq!(
+        to_string_with_config(&bools, Config::standard()).unwrap(),
+        r#"{"false":0,"true":1}"#
+    );
+    assert_eq!(
+        to_string_with_config(&map(vec![(1u64, 0u8)]), Config::standard()).unwrap(),
+        r#"{"1":0}"#
+    );
+
+    for err in [
+        to_string(&map(vec![((1u8, 2u8), 0u8)])).unwrap_err(),
+        to_string(&map(vec![(vec![1u8], 0u8)])).unwrap_err(),
+        to_string(&map(vec![(Some(1u8), 0u8)])).unwrap_err(),
+        to_string(&map(vec![((), 0u8)])).unwrap_err(),
+    ] {
+        assert_eq!(
+            "custom error : map key must be a string, integer, bool, char or unit variant",
+            err.to_string()
+        );
+    }
+}
+
+#[test]
+#[wasm_bindgen_test]
+fn test_recursion_limit() {
+    struct Forever;
+
+    impl serde::Serialize for Forever {
+        fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+            use serde::ser::SerializeSeq;
+            let mut seq = serializer.serialize_seq(Some(1))?;
+            seq.serialize_element(self)?;
+            seq.end()
+        }
+    }
+
+    match to_string(&Forever).unwrap_err() {
+        json4web::Error::RecursionLimitExceeded => {}
+        other => panic!("{:?}", other),
+    }
+
+    let nested = vec![vec![vec![1u8]]];
+    let mut serializer = Serializer::new(Vec::new()).max_depth(3);
+    serde::Serialize::serialize(&nested, &mut serializer).unwrap();
+    assert_eq!(serializer.into_inner(), b"[[[1]]]");
+    let mut serializer = Serializer::new(Vec::new()).max_depth(2);
+    assert!(serde::Serialize::serialize(&nested, &mut serializer).is_err());
+}
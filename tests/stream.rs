@@ -0,0 +1,149 @@
+#![cfg(feature = "futures")]
+#![no_std]
+#[macro_use]
+extern crate alloc;
+extern crate std;
+
+use alloc::string::{String, ToString};
+use alloc::vec::Vec;
+use bytes::Bytes;
+use core::cell::Cell;
+use futures_executor::block_on;
+use futures_util::io::AllowStdIo;
+use futures_util::{stream, StreamExt};
+use json4web::push::PushParser;
+use json4web::stream::{from_async_read, from_chunks};
+use serde_derive::Deserialize;
+
+#[derive(Deserialize, PartialEq, Debug)]
+struct Event {
+    id: u64,
+    name: String,
+}
+
+#[cfg(test)]
+fn event(id: u64, name: &str) -> Event {
+    Event {
+        id,
+        name: name.to_string(),
+    }
+}
+
+#[test]
+fn test_chunks() {
+    let chunks = vec![
+        Bytes::from_static(b"{\"id\":\"1\",\"na"),
+        Bytes::from_static(b"me\":\"a\"}\n{\"id\":\"2\",\"name\":\"b\"}"),
+        Bytes::from_static(b"\n"),
+    ];
+    let events: Vec<_> = block_on(
+        from_chunks::<_, Event>(stream::iter(chunks), PushParser::new())
+            .map(|event| event.unwrap())
+            .collect(),
+    );
+    assert_eq!(events, vec![event(1, "a"), event(2, "b")]);
+
+    let chunks = vec!["[{\"id\":\"1\",\"name\":\"a\"},", "{\"id\":2}]"];
+    let results: Vec<_> = block_on(
+        from_chunks::<_, Event>(stream::iter(chunks), PushParser::array_elements()).collect(),
+    );
+    assert_eq!(2, results.len());
+    assert_eq!(&event(1, "a"), results[0].as_ref().unwrap());
+    match results[1].as_ref().unwrap_err().kind() {
+        json4web::Error::UnexpectedToken('2') => {}
+        other => panic!("{:?}", other),
+    }
+    assert_eq!(
+        "[1].id",
+        results[1].as_ref().unwrap_err().path().unwrap().to_string()
+    );
+
+    let pulled = Cell::new(0);
+    let chunks = vec![
+        "{\"id\":\"1\",\"name\":\"a\"}",
+        "]",
+        "{\"id\":\"2\"",
+        ",\"name\":\"b\"}",
+    ];
+    let results: Vec<_> = block_on(
+        from_chunks::<_, Event>(
+            stream::iter(chunks).inspect(|_| pulled.set(pulled.get() + 1)),
+            PushParser::new(),
+        )
+        .collect(),
+    );
+    assert_eq!(2, results.len());
+    assert!(results[1].is_err());
+    assert_eq!(2, pulled.get());
+}
+
+#[test]
+fn test_large_chunk() {
+    let mut j = String::new();
+    for id in 0..200_000u64 {
+        j.push_str(&format!("{{\"id\":\"{}\",\"name\":\"n\"}}\n", id));
+    }
+    let started = std::time::Instant::now();
+    let chunks = vec![Bytes::from(j)];
+    let count = block_on(
+        from_chunks::<_, Event>(stream::iter(chunks), PushParser::new())
+            .enumerate()
+            .map(|(id, event)| assert_eq!(id as u64, event.unwrap().id))
+            .count(),
+    );
+    assert_eq!(200_000, count);
+    assert!(started.elapsed() < std::time::Duration::from_secs(8));
+}
+
+#[test]
+fn test_async_read() {
+    let input = "[{\"id\":\"1\",\"name\":\"a\"}, {\"id\":\"2\",\"name\":\"b\"}]";
+    let reader = AllowStdIo::new(input.as_bytes());
+    let events: Vec<_> = block_on(
+        from_async_read::<_, Event>(reader, PushParser::array_elements())
+            .map(|event| event.unwrap())
+            .collect(),
+    );
+    assert_eq!(events, vec![event(1, "a"), event(2, "b")]);
+
+    let reader = AllowStdIo::new("[{\"id\":\"1\",\"name\":\"a\"}".as_bytes());
+    let results: Vec<_> =
+        block_on(from_async_read::<_, Event>(reader, PushParser::array_elements()).collect());
+    assert_eq!(2, results.len());
+    match results[1].as_ref().unwrap_err().kind() {
+        json4web::Error::UnexpectedEnd => {}
+        other => panic!("{:?}", other),
+    }
+
+    struct Failing;
+    impl std::io::Read for Failing {
+        fn read(&mut self, _buf: &mut [u8]) -> std::io::Result<usize> {
+            Err(std::io::Error::other("reset"))
+        }
+    }
+    let results: Vec<_> = block_on(
+        from_async_read::<_, Event>(AllowStdIo::new(Failing), PushParser::new()).collect(),
+    );
+    assert_eq!(1, results.len());
+    match results[0].as_ref().unwrap_err() {
+        json4web::Error::Io(err) => assert_eq!(err.kind(), std::io::ErrorKind::Other),
+        other => panic!("{:?}", other),
+    }
+
+    /// Hands out a few `]` and counts the reads.
+    struct Malformed<'a>(&'a Cell<usize>);
+    impl std::io::Read for Malformed<'_> {
+        fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+            self.0.set(self.0.get() + 1);
+            buf[0] = b']';
+            Ok(if self.0.get() < 3 { 1 } else { 0 })
+        }
+    }
+    let reads = Cell::new(0);
+    let results: Vec<_> = block_on(
+        from_async_read::<_, Event>(AllowStdIo::new(Malformed(&reads)), PushParser::new())
+            .collect(),
+    );
+    assert_eq!(1, results.len());
+    assert_eq!(1, reads.get());
+}
@@ -0,0 +1,210 @@
+#![no_std]
+#[macro_use]
+extern crate alloc;
+#[macro_use]
+extern crate wasm_bindgen_test;
+
+use alloc::borrow::ToOwned;
+use alloc::string::{String, ToString};
+use alloc::vec::Vec;
+use json4web::{de, from_value, json, ser, to_value, Map, Number, Value};
+use serde_derive::{Deserialize, Serialize};
+
+#[test]
+#[wasm_bindgen_test]
+fn test_parse() {
+    let v: Value = de::from_str(r#"{"a":[1,-2,1.5,"3",null,true,{}],"b":{"c":"d"}}"#).unwrap();
+    assert_eq!(v["a"][0], Value::Number(Number::from(1u64)));
+    assert_eq!(v["a"][1], Value::Number(Number::from(-2i64)));
+    assert_eq!(v["a"][2].as_f64(), Some(1.5));
+    assert_eq!(v["a"][3], Value::String("3".to_owned()));
+    assert!(v["a"][4].is_null());
+    assert_eq!(v["a"][5], Value::Bool(true));
+    assert_eq!(v["a"][6], Value::Object(Map::new()));
+    assert_eq!(v["b"]["c"], "d");
+    assert!(v["missing"]["deeper"][3].is_null());
+    assert_eq!(v.get("b").and_then(|b| b.get("c")), Some(&json!("d")));
+    assert_eq!(v.get("a").and_then(|a| a.get(7)), None);
+    assert_eq!(v.get(0), None);
+}
+
+#[test]
+#[wasm_bindgen_test]
+fn test_conventions() {
+    let v: Value = de::from_str(r#"{"flag":1,"off":0,"big":"12345678901234","neg":"-5"}"#).unwrap();
+    assert_eq!(v["flag"].as_bool(), Some(true));
+    assert_eq!(v["off"].as_bool(), Some(false));
+    assert_eq!(v["big"].as_u64(), Some(12345678901234));
+    assert_eq!(v["big"].as_i64(), Some(12345678901234));
+    assert_eq!(v["neg"].as_i64(), Some(-5));
+    assert_eq!(v["neg"].as_u64(), None);
+    assert_eq!(Value::Null.as_f64().map(f64::is_nan), Some(true));
+
+    assert_eq!(to_value(&true).unwrap(), Value::Bool(true));
+    assert_eq!(to_value(&5u64).unwrap(), Value::String("5".to_owned()));
+    assert_eq!(to_value(&-5i64).unwrap(), Value::String("-5".to_owned()));
+    assert_eq!(to_value(&5u32).unwrap(), Value::Number(Number::from(5u64)));
+    assert_eq!(to_value(&f64::NAN).unwrap(), Value::Null);
+    let bytes = serde_bytes::Bytes::new(&[0xfb, 0xff]);
+    assert_eq!(to_value(&bytes).unwrap(), Value::String("-_8=".to_owned()));
+    assert_eq!(
+        from_value::<serde_bytes::ByteBuf>(json!("-_8=")).unwrap(),
+        bytes.to_vec()
+    );
+    assert_eq!(ser::to_string(&json!(true)).unwrap(), "1");
+    assert_eq!(ser::to_string(&json!(5u64)).unwrap(), r#""5""#);
+    assert_eq!(ser::to_string(&json!(5)).unwrap(), "5");
+    assert_eq!(
+        ser::to_string(&Value::Number(Number::from(5_000_000_000u64))).unwrap(),
+        r#""5000000000""#
+    );
+}
+
+#[derive(Serialize, Deserialize, PartialEq, Debug)]
+enum E {
+    Unit,
+    Newtype(u32),
+    Tuple(u32, u32),
+    Struct { a: u32 },
+}
+
+#[derive(Serialize, Deserialize, PartialEq, Debug)]
+struct Record {
+    id: u64,
+    small: i32,
+    flag: bool,
+    ratio: f64,
+    name: String,
+    tags: Vec<String>,
+    #[serde(with = "serde_bytes")]
+    blob: Vec<u8>,
+    parent: Option<i64>,
+    kinds: Vec<E>,
+}
+
+fn record() -> Record {
+    Record {
+        id: 1 << 60,
+        small: -3,
+        flag: true,
+        ratio: 0.25,
+        name: "x\"y".to_owned(),
+        tags: vec!["a".to_owned()],
+        blob: b"bytes".to_vec(),
+        parent: None,
+        kinds: vec![E::Unit, E::Newtype(1), E::Tuple(1, 2), E::Struct { a: 1 }],
+    }
+}
+
+#[test]
+#[wasm_bindgen_test]
+fn test_roundtrip() {
+    let value = to_value(&record()).unwrap();
+    assert_eq!(value["id"], Value::String((1u64 << 60).to_string()));
+    assert_eq!(value["kinds"][2], json!({"Tuple": [1, 2]}));
+    assert_eq!(record(), from_value::<Record>(value.clone()).unwrap());
+
+    let j = ser::to_string(&record()).unwrap();
+    let parsed: Value = de::from_str(&j).unwrap();
+    assert_eq!(record(), from_value::<Record>(parsed.clone()).unwrap());
+    let j = ser::to_string(&parsed).unwrap();
+    assert_eq!(j, ser::to_string(&value).unwrap());
+    assert_eq!(parsed, de::from_str::<Value>(&j).unwrap());
+}
+
+#[test]
+#[wasm_bindgen_test]
+fn test_map_keys() {
+    use alloc::collections::BTreeMap;
+
+    let ints: BTreeMap<u32, u32> = vec![(1, 2), (30, 4)].into_iter().collect();
+    let value = to_value(&ints).unwrap();
+    assert_eq!(value, json!({"1": 2, "30": 4}));
+    assert_eq!(ints, from_value::<BTreeMap<u32, u32>>(value).unwrap());
+
+    let signed: BTreeMap<i64, bool> = vec![(-1, true), (1 << 60, false)].into_iter().collect();
+    let value = to_value(&signed).unwrap();
+    assert_eq!(value["-1"], Value::Bool(true));
+    assert_eq!(signed, from_value::<BTreeMap<i64, bool>>(value).unwrap());
+
+    let bools: BTreeMap<bool, u8> = vec![(false, 0), (true, 1)].into_iter().collect();
+    let value = to_value(&bools).unwrap();
+    assert_eq!(value, json!({"0": 0, "1": 1}));
+    assert_eq!(bools, from_value::<BTreeMap<bool, u8>>(value).unwrap());
+    assert_eq!(
+        bools,
+        from_value::<BTreeMap<bool, u8>>(json!({"false": 0, "true": 1})).unwrap()
+    );
+
+    assert!(from_value::<BTreeMap<u32, u32>>(json!({"x": 1})).is_err());
+    assert_eq!(
+        ser::to_string(&to_value(&ints).unwrap()).unwrap(),
+        ser::to_string(&ints).unwrap()
+    );
+}
+
+#[test]
+#[wasm_bindgen_test]
+fn test_macro() {
+    let name = "n";
+    let value = json!({
+        "id": 7,
+        "neg": -1,
+        "sum": 1 + 2,
+        "name": name,
+        "list": [1, "two", [3], {"four": 4}, null, false,],
+        "empty": [],
+        "nested": {},
+    });
+    assert_eq!(value["id"], 7i64);
+    assert_eq!(value["neg"], -1i64);
+    assert_eq!(value["sum"], 3i64);
+    assert_eq!(value["name"], "n");
+    assert_eq!(value["list"][1], "two");
+    assert_eq!(value["list"][2][0], 3u64);
+    assert_eq!(value["list"][3]["four"], 4u64);
+    assert!(value["list"][4].is_null());
+    assert_eq!(value["list"][5], Value::Bool(false));
+    assert_eq!(value["empty"].as_array().map(Vec::len), Some(0));
+    assert_eq!(value["nested"].as_object().map(Map::len), Some(0));
+    assert_eq!(
+        ser::to_string(&value).unwrap(),
+        r#"{"empty":[],"id":7,"list":[1,"two",[3],{"four":4},null,0],"name":"n","neg":-1,"nested":{},"sum":3}"#
+    );
+}
+
+#[test]
+#[wasm_bindgen_test]
+fn test_index_mut() {
+    let mut value = json!({"a": [1, 2]});
+    value["a"][1] = json!("x");
+    *value.get_mut("a").unwrap() = json!(null);
+    assert_eq!(value, json!({ "a": null }));
+}
+
+#[test]
+#[wasm_bindgen_test]
+fn test_errors() {
+    assert!(from_value::<bool>(json!(2)).is_err());
+    assert!(from_value::<u64>(json!("x")).is_err());
+    assert!(from_value::<E>(json!({"Unit": null, "Newtype": 1})).is_err());
+    assert!(to_value(&{
+        let mut map = alloc::collections::BTreeMap::new();
+        map.insert(vec![1], 1);
+        map
+    })
+    .is_err());
+    struct Forever;
+    impl serde::Serialize for Forever {
+        fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+            use serde::ser::SerializeSeq;
+            let mut seq = serializer.serialize_seq(Some(1))?;
+            seq.serialize_element(self)?;
+            seq.end()
+        }
+    }
+    match to_value(&Forever).unwrap_err() {
+        json4web::Error::RecursionLimitExceeded => {}
+        other => panic!("{:?}", other),
+    }
+}